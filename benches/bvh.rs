@@ -0,0 +1,123 @@
+//! BVH build and traversal throughput on tessellated spheres of increasing size, for the
+//! binary BVH and the four-wide BVH collapsed from it.
+//!
+//! `cargo bench --bench bvh [-- TRIANGLES...]`
+
+use std::time::Instant;
+
+use rand::rngs::SmallRng;
+use rand::{Rng, SeedableRng};
+use raytrace::material::MaterialId;
+use raytrace::math::{consts::PI, Float};
+use raytrace::mesh::TriangleMesh;
+use raytrace::ray::Ray;
+use raytrace::vector::Vector;
+use raytrace::wide_bvh::WideBvh;
+
+const RAY_COUNT: usize = 200_000;
+
+/// A unit sphere split into roughly `triangles` triangles, with radial noise on the scale of
+/// a triangle so they are not all alike.
+fn sphere_mesh(triangles: usize, rng: &mut SmallRng) -> TriangleMesh {
+    let rings = ((triangles as Float / 4.0).sqrt() as usize).max(2);
+    let segments = 2 * rings;
+    let noise = 0.2 * PI / rings as Float;
+    let mut positions = Vec::with_capacity((rings + 1) * segments);
+    for ring in 0..=rings {
+        let theta = ring as Float / rings as Float * PI;
+        for segment in 0..segments {
+            let phi = segment as Float / segments as Float * 2.0 * PI;
+            let radius = 1.0 + rng.gen_range(-noise..noise);
+            positions.push(Vector::new(theta.sin() * phi.cos(), theta.cos(), theta.sin() * phi.sin()) * radius);
+        }
+    }
+    let mut indices = Vec::with_capacity(2 * rings * segments);
+    for ring in 0..rings {
+        for segment in 0..segments {
+            let a = (ring * segments + segment) as u32;
+            let b = (ring * segments + (segment + 1) % segments) as u32;
+            let (c, d) = (a + segments as u32, b + segments as u32);
+            indices.push([a, c, b]);
+            indices.push([b, c, d]);
+        }
+    }
+    TriangleMesh::new(positions, indices, MaterialId::default())
+}
+
+fn random_rays(rng: &mut SmallRng) -> Vec<Ray> {
+    let mut random_point = |scale: Float| Vector::new(rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0)) * scale;
+    (0..RAY_COUNT)
+        .map(|_| {
+            let origin = random_point(3.0);
+            let target = random_point(0.9);
+            Ray {
+                origin,
+                direction: (target - origin).normalize(),
+            }
+        })
+        .collect()
+}
+
+/// Runs `count` over all rays, returning its result and the rate in millions of rays a second.
+fn measure(count: impl FnOnce() -> usize) -> (usize, f64) {
+    let start = Instant::now();
+    let count = count();
+    (count, RAY_COUNT as f64 / start.elapsed().as_secs_f64() / 1e6)
+}
+
+fn main() {
+    let sizes = std::env::args().skip(1).filter_map(|argument| argument.parse::<usize>().ok()).collect::<Vec<usize>>();
+    let sizes = if sizes.is_empty() { vec![10_000, 100_000, 1_000_000, 4_000_000] } else { sizes };
+    let mut rng = SmallRng::seed_from_u64(1);
+    let rays = random_rays(&mut rng);
+
+    println!("Traversal rates in Mray/s, binary / wide.");
+    println!(
+        "{:>10} {:>10} {:>9} {:>10} {:>17} {:>17}",
+        "triangles", "build ms", "SAH cost", "wide ms", "closest", "any"
+    );
+    for size in sizes {
+        let mesh = sphere_mesh(size, &mut rng);
+        let start = Instant::now();
+        let bvh = mesh.bvh();
+        let build = start.elapsed();
+        let start = Instant::now();
+        let wide = WideBvh::from_bvh(bvh);
+        let collapse = start.elapsed();
+
+        let t_max = Float::INFINITY;
+        let (hits, closest) = measure(|| {
+            rays.iter()
+                .filter(|ray| bvh.intersect(ray, 0.0, t_max, |triangle, t_max| mesh.intersect_triangle(triangle, ray, 0.0, t_max)).is_some())
+                .count()
+        });
+        let (wide_hits, wide_closest) = measure(|| {
+            rays.iter()
+                .filter(|ray| wide.intersect(ray, 0.0, t_max, |triangle, t_max| mesh.intersect_triangle(triangle, ray, 0.0, t_max)).is_some())
+                .count()
+        });
+        let (occluded, any) = measure(|| {
+            rays.iter()
+                .filter(|ray| bvh.occluded(ray, 0.0, t_max, |triangle| mesh.intersect_triangle(triangle, ray, 0.0, t_max).is_some()))
+                .count()
+        });
+        let (wide_occluded, wide_any) = measure(|| {
+            rays.iter()
+                .filter(|ray| wide.occluded(ray, 0.0, t_max, |triangle| mesh.intersect_triangle(triangle, ray, 0.0, t_max).is_some()))
+                .count()
+        });
+        assert!(hits == occluded && hits == wide_hits && hits == wide_occluded);
+
+        println!(
+            "{:>10} {:>10.1} {:>9.1} {:>10.1} {:>8.2} / {:<6.2} {:>8.2} / {:<6.2}",
+            mesh.triangle_count(),
+            build.as_secs_f64() * 1e3,
+            bvh.sah_cost(),
+            collapse.as_secs_f64() * 1e3,
+            closest,
+            wide_closest,
+            any,
+            wide_any
+        );
+    }
+}
@@ -0,0 +1,91 @@
+use crate::math::Float;
+use crate::ray::Ray;
+use crate::vector::Vector;
+
+#[derive(Copy, Clone)]
+pub struct Aabb {
+    pub min: Vector,
+    pub max: Vector,
+}
+
+impl Aabb {
+    pub fn new(min: Vector, max: Vector) -> Aabb {
+        Aabb { min, max }
+    }
+
+    pub fn empty() -> Aabb {
+        Aabb {
+            min: Vector::splat(Float::INFINITY),
+            max: Vector::splat(Float::NEG_INFINITY),
+        }
+    }
+
+    pub fn infinite() -> Aabb {
+        Aabb {
+            min: Vector::splat(Float::NEG_INFINITY),
+            max: Vector::splat(Float::INFINITY),
+        }
+    }
+
+    pub fn union(&self, other: &Aabb) -> Aabb {
+        Aabb {
+            min: self.min.min(&other.min),
+            max: self.max.max(&other.max),
+        }
+    }
+
+    /// Grows each side by `delta` so flat shapes keep a non-degenerate box.
+    pub fn padded(&self, delta: Float) -> Aabb {
+        Aabb {
+            min: self.min - Vector::splat(delta),
+            max: self.max + Vector::splat(delta),
+        }
+    }
+
+    pub fn centroid(&self) -> Vector {
+        (self.min + self.max) * 0.5
+    }
+
+    pub fn extent(&self) -> Vector {
+        self.max - self.min
+    }
+
+    /// Zero for empty boxes.
+    pub fn surface_area(&self) -> Float {
+        let extent = self.extent();
+        if extent.min_component() < 0.0 {
+            return 0.0;
+        }
+        2.0 * (extent.x * extent.y + extent.y * extent.z + extent.z * extent.x)
+    }
+
+    pub fn is_finite(&self) -> bool {
+        (0..3).all(|axis| self.min[axis].is_finite() && self.max[axis].is_finite())
+    }
+
+    /// Slab test with a precomputed reciprocal direction, returning the entry distance.
+    pub fn hit_distance(&self, origin: &Vector, inv_direction: &Vector, t_min: Float, t_max: Float) -> Option<Float> {
+        let mut t_min = t_min;
+        let mut t_max = t_max;
+        for axis in 0..3 {
+            let mut t0 = (self.min[axis] - origin[axis]) * inv_direction[axis];
+            let mut t1 = (self.max[axis] - origin[axis]) * inv_direction[axis];
+            if inv_direction[axis] < 0.0 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+            // Comparisons are false for the NaN of an origin on a slab plane, which then never clips.
+            if t0 > t_min {
+                t_min = t0;
+            }
+            if t1 < t_max {
+                t_max = t1;
+            }
+        }
+        (t_min <= t_max).then_some(t_min)
+    }
+
+    pub fn hit(&self, ray: &Ray, t_min: Float, t_max: Float) -> bool {
+        let inv_direction = Vector::new(1.0 / ray.direction.x, 1.0 / ray.direction.y, 1.0 / ray.direction.z);
+        self.hit_distance(&ray.origin, &inv_direction, t_min, t_max).is_some()
+    }
+}
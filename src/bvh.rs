@@ -0,0 +1,411 @@
+//! Bounding volume hierarchy over primitives identified by index.
+
+use crate::aabb::Aabb;
+use crate::hittable::HitRecord;
+use crate::math::Float;
+use crate::ray::Ray;
+use crate::vector::Vector;
+
+const BIN_COUNT: usize = 16;
+const MAX_LEAF_SIZE: usize = 8;
+/// Relative cost of a node visit against one primitive test, for the SAH.
+pub const TRAVERSAL_COST: Float = 1.0;
+/// Beyond this depth splits fall back to the object median, which bounds the traversal stack.
+const MAX_SAH_DEPTH: usize = 64;
+const STACK_SIZE: usize = 128;
+/// How far refitting may degrade the SAH cost before a full rebuild pays off.
+pub const REBUILD_COST_RATIO: Float = 1.5;
+
+/// Nodes are stored depth-first: an interior node's left child follows it directly and its
+/// right child is at `offset`. A leaf holds `count` primitives starting at `offset`.
+#[derive(Copy, Clone)]
+pub struct BvhNode {
+    pub bounds: Aabb,
+    pub offset: u32,
+    pub count: u32,
+}
+
+impl BvhNode {
+    pub fn is_leaf(&self) -> bool {
+        self.count > 0
+    }
+}
+
+pub struct Bvh {
+    nodes: Vec<BvhNode>,
+    primitives: Vec<u32>,
+    build_cost: Float,
+}
+
+#[derive(Copy, Clone)]
+struct Bin {
+    bounds: Aabb,
+    count: usize,
+}
+
+impl Bvh {
+    /// Builds with a binned surface area heuristic from the primitives' bounds.
+    pub fn build(bounds: &[Aabb]) -> Bvh {
+        let mut bvh = Bvh {
+            nodes: Vec::with_capacity((2 * bounds.len()).max(1)),
+            primitives: (0..bounds.len() as u32).collect(),
+            build_cost: 0.0,
+        };
+        let centroids = bounds.iter().map(Aabb::centroid).collect::<Vec<Vector>>();
+        if bounds.is_empty() {
+            // An empty root box is never entered, so its child links are never followed.
+            bvh.nodes.push(BvhNode {
+                bounds: Aabb::empty(),
+                offset: 0,
+                count: 0,
+            });
+        } else {
+            bvh.build_node(bounds, &centroids, 0, bounds.len(), 0);
+        }
+        bvh.build_cost = bvh.sah_cost();
+        bvh
+    }
+
+    /// Updates node bounds for primitives that moved, keeping the tree topology.
+    pub fn refit(&mut self, bounds: &[Aabb]) {
+        // Children always follow their parent, so a reverse sweep visits them first.
+        for index in (0..self.nodes.len()).rev() {
+            let node = self.nodes[index];
+            self.nodes[index].bounds = if node.is_leaf() {
+                self.leaf_primitives(&node).iter().fold(Aabb::empty(), |total, primitive| total.union(&bounds[*primitive as usize]))
+            } else if self.primitives.is_empty() {
+                Aabb::empty()
+            } else {
+                self.nodes[index + 1].bounds.union(&self.nodes[node.offset as usize].bounds)
+            };
+        }
+    }
+
+    /// Refits, or rebuilds when the refitted tree's SAH cost exceeds `max_cost_ratio` times its
+    /// cost when built, or the primitive count changed. Returns whether it rebuilt.
+    pub fn refit_or_rebuild(&mut self, bounds: &[Aabb], max_cost_ratio: Float) -> bool {
+        if bounds.len() == self.primitives.len() {
+            self.refit(bounds);
+            if self.sah_cost() <= self.build_cost * max_cost_ratio {
+                return false;
+            }
+        }
+        *self = Bvh::build(bounds);
+        true
+    }
+
+    fn build_node(&mut self, bounds: &[Aabb], centroids: &[Vector], start: usize, end: usize, depth: usize) -> usize {
+        let index = self.nodes.len();
+        let primitives = &mut self.primitives[start..end];
+        let node_bounds = primitives.iter().fold(Aabb::empty(), |total, primitive| total.union(&bounds[*primitive as usize]));
+        self.nodes.push(BvhNode {
+            bounds: node_bounds,
+            offset: start as u32,
+            count: (end - start) as u32,
+        });
+        let count = end - start;
+        if count == 1 {
+            return index;
+        }
+
+        let centroid_bounds = primitives.iter().fold(Aabb::empty(), |total, primitive| {
+            let centroid = centroids[*primitive as usize];
+            total.union(&Aabb::new(centroid, centroid))
+        });
+        let extent = centroid_bounds.extent();
+        let bin_of = |primitive: u32, axis: usize| {
+            let offset = (centroids[primitive as usize][axis] - centroid_bounds.min[axis]) / extent[axis];
+            ((offset * BIN_COUNT as Float) as usize).min(BIN_COUNT - 1)
+        };
+        let mut split = None;
+        if depth < MAX_SAH_DEPTH {
+            let mut best_cost = count as Float;
+            for axis in 0..3 {
+                if extent[axis] <= 0.0 {
+                    continue;
+                }
+                let mut bins = [Bin {
+                    bounds: Aabb::empty(),
+                    count: 0,
+                }; BIN_COUNT];
+                for primitive in primitives.iter() {
+                    let bin = &mut bins[bin_of(*primitive, axis)];
+                    bin.bounds = bin.bounds.union(&bounds[*primitive as usize]);
+                    bin.count += 1;
+                }
+
+                // Sweep from the right to get the cost of every right side, then from the left.
+                let mut right_costs = [0.0; BIN_COUNT];
+                let mut right = Bin {
+                    bounds: Aabb::empty(),
+                    count: 0,
+                };
+                for bin in (1..BIN_COUNT).rev() {
+                    right.bounds = right.bounds.union(&bins[bin].bounds);
+                    right.count += bins[bin].count;
+                    right_costs[bin] = right.bounds.surface_area() * right.count as Float;
+                }
+                let mut left = Bin {
+                    bounds: Aabb::empty(),
+                    count: 0,
+                };
+                let parent_area = node_bounds.surface_area().max(Float::MIN_POSITIVE);
+                for bin in 1..BIN_COUNT {
+                    left.bounds = left.bounds.union(&bins[bin - 1].bounds);
+                    left.count += bins[bin - 1].count;
+                    if left.count == 0 || left.count == count {
+                        continue;
+                    }
+                    let cost = TRAVERSAL_COST + (left.bounds.surface_area() * left.count as Float + right_costs[bin]) / parent_area;
+                    if cost < best_cost {
+                        best_cost = cost;
+                        split = Some((axis, bin));
+                    }
+                }
+            }
+            if split.is_none() && count <= MAX_LEAF_SIZE {
+                return index;
+            }
+        }
+
+        let middle = match split {
+            Some((axis, bin)) => {
+                let mut middle = 0;
+                for i in 0..count {
+                    if bin_of(primitives[i], axis) < bin {
+                        primitives.swap(i, middle);
+                        middle += 1;
+                    }
+                }
+                start + middle
+            }
+            // No split beats a leaf but the leaf would be too large, or the tree is too deep.
+            None => {
+                let axis = if extent.x >= extent.y && extent.x >= extent.z {
+                    0
+                } else if extent.y >= extent.z {
+                    1
+                } else {
+                    2
+                };
+                let middle = count / 2;
+                primitives.select_nth_unstable_by(middle, |a, b| {
+                    centroids[*a as usize][axis].total_cmp(&centroids[*b as usize][axis])
+                });
+                start + middle
+            }
+        };
+
+        self.build_node(bounds, centroids, start, middle, depth + 1);
+        let right = self.build_node(bounds, centroids, middle, end, depth + 1);
+        self.nodes[index].offset = right as u32;
+        self.nodes[index].count = 0;
+        index
+    }
+
+    pub fn nodes(&self) -> &[BvhNode] {
+        &self.nodes
+    }
+
+    /// Primitive indices in leaf order.
+    pub fn primitives(&self) -> &[u32] {
+        &self.primitives
+    }
+
+    pub fn bounding_box(&self) -> Aabb {
+        self.nodes[0].bounds
+    }
+
+    /// Expected cost of a random ray under the SAH, relative to one primitive test. Refitting
+    /// raises it as boxes grow and overlap.
+    pub fn sah_cost(&self) -> Float {
+        let root_area = self.nodes[0].bounds.surface_area();
+        if root_area <= 0.0 {
+            return self.primitives.len() as Float;
+        }
+        self.nodes
+            .iter()
+            .map(|node| {
+                let area = node.bounds.surface_area() / root_area;
+                if node.is_leaf() { area * node.count as Float } else { area * TRAVERSAL_COST }
+            })
+            .sum()
+    }
+
+    fn leaf_primitives(&self, node: &BvhNode) -> &[u32] {
+        &self.primitives[node.offset as usize..(node.offset + node.count) as usize]
+    }
+
+    /// Closest hit. `intersect_primitive` is called with a primitive index and the current
+    /// closest distance, and should only report hits nearer than it.
+    pub fn intersect(
+        &self,
+        ray: &Ray,
+        t_min: Float,
+        t_max: Float,
+        mut intersect_primitive: impl FnMut(usize, Float) -> Option<HitRecord>,
+    ) -> Option<HitRecord> {
+        let inv_direction = Vector::new(1.0 / ray.direction.x, 1.0 / ray.direction.y, 1.0 / ray.direction.z);
+        let mut closest = None;
+        let mut t_max = t_max;
+        self.nodes[0].bounds.hit_distance(&ray.origin, &inv_direction, t_min, t_max)?;
+
+        let mut stack = [(0u32, 0.0 as Float); STACK_SIZE];
+        let mut stack_size = 0;
+        let mut index = 0;
+        loop {
+            let node = &self.nodes[index];
+            let mut next = None;
+            if node.is_leaf() {
+                for primitive in self.leaf_primitives(node) {
+                    if let Some(hit) = intersect_primitive(*primitive as usize, t_max) {
+                        t_max = hit.t;
+                        closest = Some(hit);
+                    }
+                }
+            } else {
+                let (left, right) = (index + 1, node.offset as usize);
+                let t_left = self.nodes[left].bounds.hit_distance(&ray.origin, &inv_direction, t_min, t_max);
+                let t_right = self.nodes[right].bounds.hit_distance(&ray.origin, &inv_direction, t_min, t_max);
+                next = match (t_left, t_right) {
+                    (Some(t_left), Some(t_right)) => {
+                        let (near, far, t_far) = if t_left <= t_right { (left, right, t_right) } else { (right, left, t_left) };
+                        stack[stack_size] = (far as u32, t_far);
+                        stack_size += 1;
+                        Some(near)
+                    }
+                    (Some(_), None) => Some(left),
+                    (None, Some(_)) => Some(right),
+                    (None, None) => None,
+                };
+            }
+            match next {
+                Some(next) => index = next,
+                None => loop {
+                    if stack_size == 0 {
+                        return closest;
+                    }
+                    stack_size -= 1;
+                    let (candidate, t_entry) = stack[stack_size];
+                    // Skip subtrees that a later hit has moved out of range.
+                    if t_entry <= t_max {
+                        index = candidate as usize;
+                        break;
+                    }
+                },
+            }
+        }
+    }
+
+    /// Any hit, for shadow rays: stops at the first primitive for which `occludes` is true.
+    pub fn occluded(&self, ray: &Ray, t_min: Float, t_max: Float, mut occludes: impl FnMut(usize) -> bool) -> bool {
+        let inv_direction = Vector::new(1.0 / ray.direction.x, 1.0 / ray.direction.y, 1.0 / ray.direction.z);
+        if self.nodes[0].bounds.hit_distance(&ray.origin, &inv_direction, t_min, t_max).is_none() {
+            return false;
+        }
+        let mut stack = [0u32; STACK_SIZE];
+        let mut stack_size = 0;
+        let mut index = 0;
+        loop {
+            let node = &self.nodes[index];
+            if node.is_leaf() {
+                if self.leaf_primitives(node).iter().any(|primitive| occludes(*primitive as usize)) {
+                    return true;
+                }
+            } else {
+                for child in [index + 1, node.offset as usize] {
+                    if self.nodes[child].bounds.hit_distance(&ray.origin, &inv_direction, t_min, t_max).is_some() {
+                        stack[stack_size] = child as u32;
+                        stack_size += 1;
+                    }
+                }
+            }
+            if stack_size == 0 {
+                return false;
+            }
+            stack_size -= 1;
+            index = stack[stack_size] as usize;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::SmallRng;
+    use rand::{Rng, SeedableRng};
+
+    use super::*;
+    use crate::hittable::Hittable;
+    use crate::material::MaterialId;
+    use crate::sphere::Sphere;
+
+    fn random_vector(rng: &mut SmallRng, extent: Float) -> Vector {
+        Vector::new(rng.gen_range(-extent..extent), rng.gen_range(-extent..extent), rng.gen_range(-extent..extent))
+    }
+
+    fn spheres(rng: &mut SmallRng) -> Vec<Sphere> {
+        (0..500)
+            .map(|_| Sphere {
+                center: random_vector(rng, 50.0),
+                radius: rng.gen_range(0.2..3.0),
+                material: MaterialId::default(),
+            })
+            .collect()
+    }
+
+    fn rays(rng: &mut SmallRng) -> Vec<Ray> {
+        (0..20_000)
+            .map(|_| Ray {
+                origin: random_vector(rng, 80.0),
+                direction: random_vector(rng, 1.0),
+            })
+            .collect()
+    }
+
+    fn closest(spheres: &[Sphere], ray: &Ray, t_max: Float) -> Option<Float> {
+        spheres.iter().filter_map(|sphere| sphere.intersect(ray, 0.0, t_max)).map(|hit| hit.t).min_by(Float::total_cmp)
+    }
+
+    /// Compares closest hits over the whole ray and shadow tests over part of it with testing
+    /// every sphere.
+    fn assert_matches_brute_force(bvh: &Bvh, spheres: &[Sphere], rays: &[Ray]) {
+        for ray in rays {
+            let actual = bvh.intersect(ray, 0.0, Float::INFINITY, |primitive, t_max| spheres[primitive].intersect(ray, 0.0, t_max));
+            assert_eq!(actual.map(|hit| hit.t), closest(spheres, ray, Float::INFINITY));
+            let occluded = bvh.occluded(ray, 0.0, 40.0, |primitive| spheres[primitive].occluded(ray, 0.0, 40.0));
+            assert_eq!(occluded, closest(spheres, ray, 40.0).is_some());
+        }
+    }
+
+    fn bounds(spheres: &[Sphere]) -> Vec<Aabb> {
+        spheres.iter().map(Sphere::bounding_box).collect()
+    }
+
+    #[test]
+    fn matches_brute_force() {
+        let mut rng = SmallRng::seed_from_u64(3);
+        let spheres = spheres(&mut rng);
+        let bvh = Bvh::build(&bounds(&spheres));
+        assert!(bvh.sah_cost().is_finite());
+        assert_matches_brute_force(&bvh, &spheres, &rays(&mut rng));
+    }
+
+    #[test]
+    fn matches_brute_force_after_refit() {
+        let mut rng = SmallRng::seed_from_u64(5);
+        let mut spheres = spheres(&mut rng);
+        let mut bvh = Bvh::build(&bounds(&spheres));
+        for sphere in &mut spheres {
+            sphere.center += random_vector(&mut rng, 5.0);
+        }
+        bvh.refit(&bounds(&spheres));
+        assert_matches_brute_force(&bvh, &spheres, &rays(&mut rng));
+
+        // Moving every sphere somewhere unrelated degrades the refitted tree enough to rebuild it.
+        for sphere in &mut spheres {
+            sphere.center = random_vector(&mut rng, 50.0);
+        }
+        assert!(bvh.refit_or_rebuild(&bounds(&spheres), REBUILD_COST_RATIO));
+        assert_matches_brute_force(&bvh, &spheres, &rays(&mut rng));
+    }
+}
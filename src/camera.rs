@@ -0,0 +1,405 @@
+use image::GrayImage;
+use rand::Rng;
+
+use crate::math::consts::PI;
+use crate::math::Float;
+use crate::ray::Ray;
+use crate::vector::Vector;
+
+#[derive(Clone)]
+pub enum Aperture {
+    Circle,
+    /// Regular polygon with `blades` sides, rotated by `rotation` radians.
+    Polygon { blades: u32, rotation: Float },
+    Mask(ApertureMask),
+}
+
+/// Custom aperture shape sampled in proportion to pixel brightness.
+#[derive(Clone)]
+pub struct ApertureMask {
+    width: u32,
+    height: u32,
+    cdf: Vec<Float>,
+}
+
+impl ApertureMask {
+    pub fn from_image(image: &GrayImage) -> ApertureMask {
+        let mut total = 0.0;
+        let cdf = image
+            .pixels()
+            .map(|pixel| {
+                total += pixel.0[0] as Float;
+                total
+            })
+            .collect::<Vec<Float>>();
+        let cdf = if total > 0.0 { cdf.iter().map(|c| c / total).collect() } else { Vec::new() };
+        ApertureMask {
+            width: image.width(),
+            height: image.height(),
+            cdf,
+        }
+    }
+
+    fn sample(&self, rng: &mut impl Rng) -> (Float, Float) {
+        if self.cdf.is_empty() {
+            return (0.0, 0.0);
+        }
+        // Zero-weight pixels repeat the previous sum, so the first sum above `target` is never one.
+        let target = rng.gen::<Float>();
+        let index = self.cdf.partition_point(|&c| c <= target).min(self.cdf.len() - 1);
+        let px = (index as u32 % self.width) as Float + rng.gen::<Float>();
+        let py = (index as u32 / self.width) as Float + rng.gen::<Float>();
+        let size = self.width.max(self.height) as Float;
+        (
+            (2.0 * px - self.width as Float) / size,
+            (self.height as Float - 2.0 * py) / size,
+        )
+    }
+}
+
+impl Aperture {
+    /// Returns a point inside the aperture shape, scaled to fit the unit disk.
+    fn sample(&self, rng: &mut impl Rng) -> (Float, Float) {
+        match self {
+            Aperture::Circle => {
+                let r = rng.gen::<Float>().sqrt();
+                let phi = 2.0 * PI * rng.gen::<Float>();
+                (r * phi.cos(), r * phi.sin())
+            }
+            Aperture::Polygon { blades, rotation } => {
+                let blades = (*blades).max(3);
+                let step = 2.0 * PI / blades as Float;
+                let blade = rng.gen_range(0..blades) as Float;
+                let a0 = rotation + blade * step;
+                let a1 = a0 + step;
+                let mut b0 = rng.gen::<Float>();
+                let mut b1 = rng.gen::<Float>();
+                if b0 + b1 > 1.0 {
+                    b0 = 1.0 - b0;
+                    b1 = 1.0 - b1;
+                }
+                (b0 * a0.cos() + b1 * a1.cos(), b0 * a0.sin() + b1 * a1.sin())
+            }
+            Aperture::Mask(mask) => mask.sample(rng),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub enum FisheyeMapping {
+    /// Image radius proportional to the angle from the optical axis.
+    Equidistant,
+    /// Image radius proportional to `sin(theta / 2)`, preserving solid angle.
+    Equisolid,
+}
+
+#[derive(Clone)]
+pub enum Projection {
+    Perspective { vfov_degrees: Float, aspect_ratio: Float },
+    /// Full 360 by 180 degree latitude-longitude panorama.
+    Equirectangular,
+    /// Circular fisheye inscribed in the image height.
+    Fisheye { fov_degrees: Float, mapping: FisheyeMapping, aspect_ratio: Float },
+    Cylindrical { hfov_degrees: Float, vfov_degrees: Float },
+}
+
+#[derive(Clone)]
+pub struct Camera {
+    eye: Vector,
+    u: Vector,
+    v: Vector,
+    w: Vector,
+    projection: Projection,
+    lens_radius: Float,
+    focus_distance: Float,
+    aperture: Aperture,
+    eye_offset: Float,
+    convergence: Float,
+}
+
+impl Camera {
+    pub fn new(eye: Vector, look_at: Vector, up: Vector, vfov_degrees: Float, aspect_ratio: Float) -> Camera {
+        Camera::with_projection(eye, look_at, up, Projection::Perspective { vfov_degrees, aspect_ratio })
+    }
+
+    pub fn with_projection(eye: Vector, look_at: Vector, up: Vector, projection: Projection) -> Camera {
+        let w = (eye - look_at).normalize();
+        let u = up.cross(&w).normalize();
+        let v = w.cross(&u);
+
+        Camera {
+            eye,
+            u,
+            v,
+            w,
+            projection,
+            lens_radius: 0.0,
+            focus_distance: 1.0,
+            aperture: Aperture::Circle,
+            eye_offset: 0.0,
+            convergence: Float::INFINITY,
+        }
+    }
+
+    /// Turns the pinhole into a thin lens focused `focus_distance` along the view direction.
+    pub fn with_thin_lens(mut self, lens_radius: Float, focus_distance: Float, aperture: Aperture) -> Camera {
+        self.lens_radius = lens_radius;
+        self.focus_distance = focus_distance;
+        self.aperture = aperture;
+        self
+    }
+
+    /// Moves the eye `eye_offset` to the right (negative for the left eye) of a stereo pair.
+    /// Rays converge at `convergence` distance, so objects there have zero parallax; panoramic
+    /// projections offset the eye per column to produce omni-directional stereo.
+    pub fn with_stereo_offset(mut self, eye_offset: Float, convergence: Float) -> Camera {
+        self.eye_offset = eye_offset;
+        self.convergence = convergence;
+        self
+    }
+
+    /// Direction through the image point in camera space as `(right, up, forward)`, or `None`
+    /// when the point lies outside the projection (e.g. the corners of a fisheye frame).
+    fn local_direction(&self, s: Float, t: Float) -> Option<(Float, Float, Float)> {
+        match &self.projection {
+            Projection::Perspective { vfov_degrees, aspect_ratio } => {
+                let half_height = (vfov_degrees.to_radians() / 2.0).tan();
+                let half_width = aspect_ratio * half_height;
+                Some(((2.0 * s - 1.0) * half_width, (2.0 * t - 1.0) * half_height, 1.0))
+            }
+            Projection::Equirectangular => {
+                let longitude = (s - 0.5) * 2.0 * PI;
+                let latitude = (t - 0.5) * PI;
+                Some((latitude.cos() * longitude.sin(), latitude.sin(), latitude.cos() * longitude.cos()))
+            }
+            Projection::Fisheye { fov_degrees, mapping, aspect_ratio } => {
+                let x = (2.0 * s - 1.0) * aspect_ratio;
+                let y = 2.0 * t - 1.0;
+                let r = (x * x + y * y).sqrt();
+                if r > 1.0 {
+                    return None;
+                }
+                let half_fov = fov_degrees.to_radians() / 2.0;
+                let theta = match mapping {
+                    FisheyeMapping::Equidistant => r * half_fov,
+                    FisheyeMapping::Equisolid => 2.0 * (r * (half_fov / 2.0).sin()).clamp(-1.0, 1.0).asin(),
+                };
+                let phi = y.atan2(x);
+                Some((theta.sin() * phi.cos(), theta.sin() * phi.sin(), theta.cos()))
+            }
+            Projection::Cylindrical { hfov_degrees, vfov_degrees } => {
+                let longitude = (s - 0.5) * hfov_degrees.to_radians();
+                let height = (2.0 * t - 1.0) * (vfov_degrees.to_radians() / 2.0).tan();
+                let length = (1.0 + height * height).sqrt();
+                Some((longitude.sin() / length, height / length, longitude.cos() / length))
+            }
+        }
+    }
+
+    /// `s` runs left to right and `t` bottom to top across the image, both in `[0, 1]`.
+    pub fn get_ray(&self, s: Float, t: Float, rng: &mut impl Rng) -> Option<Ray> {
+        let (right, up, forward) = self.local_direction(s, t)?;
+        let mut direction = self.u * right + self.v * up - self.w * forward;
+        let mut eye = self.eye;
+        if self.eye_offset != 0.0 {
+            let offset = match self.projection {
+                Projection::Equirectangular | Projection::Cylindrical { .. } => {
+                    let horizontal = (right * right + forward * forward).sqrt();
+                    if horizontal > 0.0 {
+                        self.u * (forward / horizontal) + self.w * (right / horizontal)
+                    } else {
+                        self.u
+                    }
+                }
+                _ => self.u,
+            } * self.eye_offset;
+            eye += offset;
+            direction -= offset / self.convergence;
+        }
+
+        if self.lens_radius <= 0.0 {
+            return Some(Ray {
+                origin: eye,
+                direction: direction.normalize(),
+            });
+        }
+
+        let (lens_x, lens_y) = self.aperture.sample(rng);
+        let lens_offset = self.u * (lens_x * self.lens_radius) + self.v * (lens_y * self.lens_radius);
+        let focus_point = eye + direction * self.focus_distance;
+        let origin = eye + lens_offset;
+        Some(Ray {
+            origin,
+            direction: (focus_point - origin).normalize(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use image::Luma;
+    use rand::rngs::mock::StepRng;
+    use rand::rngs::SmallRng;
+    use rand::SeedableRng;
+
+    use super::*;
+
+    fn assert_close(actual: Vector, expected: Vector) {
+        assert!((actual - expected).length() < 1e-5, "{actual:?} != {expected:?}");
+    }
+
+    /// Camera at the origin looking down -z with +y up.
+    fn looking_down_z(projection: Projection) -> Camera {
+        Camera::with_projection(Vector::zero(), Vector::new(0.0, 0.0, -1.0), Vector::new(0.0, 1.0, 0.0), projection)
+    }
+
+    fn direction(camera: &Camera, s: Float, t: Float) -> Vector {
+        camera.get_ray(s, t, &mut SmallRng::seed_from_u64(1)).unwrap().direction
+    }
+
+    #[test]
+    fn centre_ray_points_at_the_target() {
+        let eye = Vector::new(1.0, 2.0, 3.0);
+        let look_at = Vector::new(-2.0, 0.0, -1.0);
+        let camera = Camera::new(eye, look_at, Vector::new(0.0, 1.0, 0.0), 50.0, 1.5);
+        let ray = camera.get_ray(0.5, 0.5, &mut SmallRng::seed_from_u64(1)).unwrap();
+        assert_eq!(ray.origin, eye);
+        assert_close(ray.direction, (look_at - eye).normalize());
+    }
+
+    #[test]
+    fn corners_follow_the_field_of_view_and_aspect_ratio() {
+        let camera = Camera::new(Vector::zero(), Vector::new(0.0, 0.0, -1.0), Vector::new(0.0, 1.0, 0.0), 60.0, 2.0);
+        let half_height = (30.0 as Float).to_radians().tan();
+        assert_close(direction(&camera, 0.0, 0.0), Vector::new(-2.0 * half_height, -half_height, -1.0).normalize());
+        assert_close(direction(&camera, 1.0, 1.0), Vector::new(2.0 * half_height, half_height, -1.0).normalize());
+        // The bottom and top edge centres span the vertical field of view.
+        let angle = direction(&camera, 0.5, 0.0).dot(&direction(&camera, 0.5, 1.0)).acos();
+        assert!((angle.to_degrees() - 60.0).abs() < 1e-3, "{angle}");
+    }
+
+    #[test]
+    fn up_vector_sets_the_top_of_the_image() {
+        let up = Camera::new(Vector::zero(), Vector::new(0.0, 0.0, -1.0), Vector::new(0.0, 1.0, 0.0), 60.0, 1.0);
+        assert!(direction(&up, 0.5, 1.0).y > 0.0);
+        assert!(direction(&up, 0.5, 0.0).y < 0.0);
+        // Keeping the view direction but flipping `up` mirrors the image vertically.
+        let down = Camera::new(Vector::zero(), Vector::new(0.0, 0.0, -1.0), Vector::new(0.0, -1.0, 0.0), 60.0, 1.0);
+        assert!(direction(&down, 0.5, 1.0).y < 0.0);
+    }
+
+    #[test]
+    fn lens_samples_stay_inside_the_aperture() {
+        let mut rng = SmallRng::seed_from_u64(2);
+        for _ in 0..10_000 {
+            let (x, y) = Aperture::Circle.sample(&mut rng);
+            assert!(x * x + y * y <= 1.0 + 1e-9, "({x}, {y})");
+        }
+
+        let (blades, rotation) = (5, 0.3);
+        let aperture = Aperture::Polygon { blades, rotation };
+        let corner = |k: u32| {
+            let angle = rotation + k as Float * 2.0 * PI / blades as Float;
+            (angle.cos(), angle.sin())
+        };
+        for _ in 0..10_000 {
+            let (x, y) = aperture.sample(&mut rng);
+            // Counter-clockwise corners keep the inside to the left of every edge.
+            for k in 0..blades {
+                let ((x0, y0), (x1, y1)) = (corner(k), corner(k + 1));
+                assert!((x1 - x0) * (y - y0) - (y1 - y0) * (x - x0) >= -1e-9, "({x}, {y}) outside edge {k}");
+            }
+        }
+    }
+
+    #[test]
+    fn masks_never_sample_dark_pixels() {
+        // Only two pixels of a 4x4 mask let light through, and the first pixel is dark.
+        let mut image = GrayImage::new(4, 4);
+        image.put_pixel(1, 2, Luma([255]));
+        image.put_pixel(3, 0, Luma([64]));
+        let mask = ApertureMask::from_image(&image);
+        let pixel = |(x, y): (Float, Float)| {
+            let px = (x * 4.0 + 4.0) / 2.0;
+            let py = (4.0 - y * 4.0) / 2.0;
+            (px, py)
+        };
+        let inside = |(px, py): (Float, Float), (x, y): (u32, u32)| {
+            let (x, y) = (x as Float, y as Float);
+            px >= x - 1e-9 && px <= x + 1.0 + 1e-9 && py >= y - 1e-9 && py <= y + 1.0 + 1e-9
+        };
+
+        // A zero random number must skip the leading dark pixels.
+        assert_eq!(pixel(mask.sample(&mut StepRng::new(0, 0))), (3.0, 0.0));
+        let mut rng = SmallRng::seed_from_u64(3);
+        for _ in 0..10_000 {
+            let point = pixel(mask.sample(&mut rng));
+            assert!(inside(point, (1, 2)) || inside(point, (3, 0)), "{point:?}");
+        }
+    }
+
+    #[test]
+    fn thin_lens_rays_converge_on_the_focus_plane() {
+        let focus_distance = 4.0;
+        let camera = Camera::new(Vector::zero(), Vector::new(0.0, 0.0, -1.0), Vector::new(0.0, 1.0, 0.0), 40.0, 1.5)
+            .with_thin_lens(0.5, focus_distance, Aperture::Polygon { blades: 6, rotation: 0.0 });
+        let mut rng = SmallRng::seed_from_u64(4);
+        let mut focus_points = Vec::new();
+        for _ in 0..100 {
+            let ray = camera.get_ray(0.3, 0.8, &mut rng).unwrap();
+            let t = (-focus_distance - ray.origin.z) / ray.direction.z;
+            focus_points.push(ray.origin + ray.direction * t);
+            assert!(ray.origin.length() <= 0.5 + 1e-9 && ray.origin.z == 0.0);
+        }
+        for point in &focus_points {
+            assert_close(*point, focus_points[0]);
+        }
+        // Without the lens the same pixel looks straight at that point.
+        let pinhole = Camera::new(Vector::zero(), Vector::new(0.0, 0.0, -1.0), Vector::new(0.0, 1.0, 0.0), 40.0, 1.5);
+        assert_close(direction(&pinhole, 0.3, 0.8), focus_points[0].normalize());
+    }
+
+    #[test]
+    fn equirectangular_covers_the_full_sphere() {
+        let camera = looking_down_z(Projection::Equirectangular);
+        assert_close(direction(&camera, 0.5, 0.5), Vector::new(0.0, 0.0, -1.0));
+        assert_close(direction(&camera, 0.75, 0.5), Vector::new(1.0, 0.0, 0.0));
+        assert_close(direction(&camera, 0.25, 0.5), Vector::new(-1.0, 0.0, 0.0));
+        assert_close(direction(&camera, 0.0, 0.5), Vector::new(0.0, 0.0, 1.0));
+        assert_close(direction(&camera, 0.5, 1.0), Vector::new(0.0, 1.0, 0.0));
+        assert_close(direction(&camera, 0.5, 0.0), Vector::new(0.0, -1.0, 0.0));
+    }
+
+    #[test]
+    fn fisheye_maps_radius_to_angle() {
+        let fisheye = |mapping| looking_down_z(Projection::Fisheye { fov_degrees: 180.0, mapping, aspect_ratio: 1.0 });
+        for camera in [fisheye(FisheyeMapping::Equidistant), fisheye(FisheyeMapping::Equisolid)] {
+            assert_close(direction(&camera, 0.5, 0.5), Vector::new(0.0, 0.0, -1.0));
+            assert_close(direction(&camera, 1.0, 0.5), Vector::new(1.0, 0.0, 0.0));
+            assert_close(direction(&camera, 0.0, 0.5), Vector::new(-1.0, 0.0, 0.0));
+            assert_close(direction(&camera, 0.5, 1.0), Vector::new(0.0, 1.0, 0.0));
+            // The corners lie outside the image circle.
+            assert!(camera.get_ray(1.0, 1.0, &mut SmallRng::seed_from_u64(1)).is_none());
+        }
+
+        // Halfway to the rim of a 180 degree fisheye.
+        let off_axis = |camera: &Camera| direction(camera, 0.75, 0.5).dot(&Vector::new(0.0, 0.0, -1.0)).acos().to_degrees();
+        let equidistant = off_axis(&fisheye(FisheyeMapping::Equidistant));
+        assert!((equidistant - 45.0).abs() < 1e-3, "{equidistant}");
+        let equisolid = off_axis(&fisheye(FisheyeMapping::Equisolid));
+        let expected = 2.0 * (0.5 * (45.0 as Float).to_radians().sin()).asin().to_degrees();
+        assert!((equisolid - expected).abs() < 1e-3 && equisolid < 42.0, "{equisolid}");
+    }
+
+    #[test]
+    fn cylindrical_wraps_horizontally_and_stays_perspective_vertically() {
+        let camera = looking_down_z(Projection::Cylindrical { hfov_degrees: 180.0, vfov_degrees: 90.0 });
+        assert_close(direction(&camera, 0.5, 0.5), Vector::new(0.0, 0.0, -1.0));
+        assert_close(direction(&camera, 1.0, 0.5), Vector::new(1.0, 0.0, 0.0));
+        assert_close(direction(&camera, 0.0, 0.5), Vector::new(-1.0, 0.0, 0.0));
+        assert_close(direction(&camera, 0.5, 1.0), Vector::new(0.0, 1.0, -1.0).normalize());
+        // Rows are straight lines at a constant height on the unit cylinder.
+        let edge = direction(&camera, 1.0, 1.0);
+        assert_close(edge, Vector::new(1.0, 1.0, 0.0).normalize());
+    }
+}
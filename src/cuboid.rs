@@ -0,0 +1,172 @@
+use crate::aabb::Aabb;
+use crate::hittable::{HitRecord, Hittable, SurfaceSample};
+use crate::material::MaterialId;
+use crate::math::Float;
+use crate::ray::Ray;
+use crate::vector::Vector;
+
+/// Axis-aligned box; wrap it in an `Instance` for an oriented box. UVs span each face in `[0, 1]`.
+pub struct Cuboid {
+    pub min: Vector,
+    pub max: Vector,
+    pub material: MaterialId,
+}
+
+impl Cuboid {
+    fn face_areas(&self) -> [Float; 3] {
+        let size = self.max - self.min;
+        [size.y * size.z, size.z * size.x, size.x * size.y]
+    }
+}
+
+impl Hittable for Cuboid {
+    fn intersect(&self, ray: &Ray, t_min: Float, t_max: Float) -> Option<HitRecord> {
+        let mut t_near = Float::NEG_INFINITY;
+        let mut t_far = Float::INFINITY;
+        let mut near_axis = 0;
+        let mut far_axis = 0;
+        for axis in 0..3 {
+            let inv_d = 1.0 / ray.direction[axis];
+            let mut t0 = (self.min[axis] - ray.origin[axis]) * inv_d;
+            let mut t1 = (self.max[axis] - ray.origin[axis]) * inv_d;
+            if inv_d < 0.0 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+            if t0 > t_near {
+                t_near = t0;
+                near_axis = axis;
+            }
+            if t1 < t_far {
+                t_far = t1;
+                far_axis = axis;
+            }
+        }
+        if t_near > t_far {
+            return None;
+        }
+
+        // Rays starting inside the box hit the far side first.
+        let (t, axis) = if t_near > t_min && t_near < t_max {
+            (t_near, near_axis)
+        } else if t_far > t_min && t_far < t_max {
+            (t_far, far_axis)
+        } else {
+            return None;
+        };
+
+        let point = ray.origin + ray.direction * t;
+        let mut outward_normal = Vector::zero();
+        outward_normal[axis] = if point[axis] - self.min[axis] < self.max[axis] - point[axis] { -1.0 } else { 1.0 };
+        let size = self.max - self.min;
+        let (a, b) = ((axis + 1) % 3, (axis + 2) % 3);
+        // A flat box has zero extent along one face axis; its UVs collapse to 0 there.
+        let coordinate = |axis: usize| if size[axis] > 0.0 { (point[axis] - self.min[axis]) / size[axis] } else { 0.0 };
+        let (normal, front_face) = HitRecord::face_normal(ray, outward_normal);
+        Some(HitRecord {
+            t,
+            point,
+            normal,
+            front_face,
+            u: coordinate(a),
+            v: coordinate(b),
+            material: self.material,
+            vertex_color: None,
+            tangent: None,
+        })
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        Aabb::new(self.min, self.max)
+    }
+
+    fn sample_surface(&self, u: Float, v: Float) -> SurfaceSample {
+        let areas = self.face_areas();
+        let total = areas.iter().sum::<Float>();
+        let size = self.max - self.min;
+
+        // The first coordinate picks one of the six faces by area and is then reused within it.
+        let mut remaining = u * 2.0 * total;
+        let mut face = 5;
+        for (index, area) in areas.iter().flat_map(|a| [*a, *a]).enumerate() {
+            if remaining < area {
+                face = index;
+                break;
+            }
+            remaining -= area;
+        }
+        let axis = face / 2;
+        let (a, b) = ((axis + 1) % 3, (axis + 2) % 3);
+        let area = areas[axis];
+        let u = if area > 0.0 { (remaining / area).clamp(0.0, 1.0) } else { 0.0 };
+
+        let mut point = self.min;
+        let mut normal = Vector::zero();
+        if face % 2 == 0 {
+            normal[axis] = -1.0;
+        } else {
+            point[axis] = self.max[axis];
+            normal[axis] = 1.0;
+        }
+        point[a] += u * size[a];
+        point[b] += v * size[b];
+        SurfaceSample {
+            point,
+            normal,
+            pdf: 1.0 / (2.0 * total),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hit(origin: Vector, direction: Vector) -> Option<HitRecord> {
+        let cuboid = Cuboid {
+            min: Vector::zero(),
+            max: Vector::new(1.0, 2.0, 3.0),
+            material: MaterialId::default(),
+        };
+        cuboid.intersect(&Ray { origin, direction }, 0.0, Float::INFINITY)
+    }
+
+    #[test]
+    fn hits_the_near_face_from_outside() {
+        let front = hit(Vector::new(0.5, 1.0, -5.0), Vector::new(0.0, 0.0, 1.0)).unwrap();
+        assert_eq!((front.t, front.normal, front.front_face), (5.0, Vector::new(0.0, 0.0, -1.0), true));
+        assert_eq!((front.u, front.v), (0.5, 0.5));
+
+        let side = hit(Vector::new(4.0, 0.5, 1.5), Vector::new(-2.0, 0.0, 0.0)).unwrap();
+        assert_eq!((side.t, side.normal, side.front_face), (1.5, Vector::new(1.0, 0.0, 0.0), true));
+        assert_eq!((side.u, side.v), (0.25, 0.5));
+    }
+
+    #[test]
+    fn hits_the_far_face_from_inside() {
+        let inside = hit(Vector::new(0.5, 0.5, 1.0), Vector::new(1.0, 0.0, 0.0)).unwrap();
+        assert_eq!((inside.t, inside.normal, inside.front_face), (0.5, Vector::new(-1.0, 0.0, 0.0), false));
+        assert_eq!(inside.u, 0.25);
+        assert!((inside.v - 1.0 / 3.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn misses_beside_and_behind() {
+        assert!(hit(Vector::new(2.0, 1.0, -5.0), Vector::new(0.0, 0.0, 1.0)).is_none());
+        assert!(hit(Vector::new(0.5, 1.0, -5.0), Vector::new(0.0, 0.0, -1.0)).is_none());
+    }
+
+    #[test]
+    fn flat_boxes_have_finite_uvs() {
+        let flat = Cuboid {
+            min: Vector::zero(),
+            max: Vector::new(2.0, 0.0, 4.0),
+            material: MaterialId::default(),
+        };
+        let ray = Ray {
+            origin: Vector::new(0.5, 0.0, -1.0),
+            direction: Vector::new(0.0, 0.0, 1.0),
+        };
+        let hit = flat.intersect(&ray, 0.0, Float::INFINITY).unwrap();
+        assert_eq!((hit.t, hit.u, hit.v), (1.0, 0.25, 0.0));
+    }
+}
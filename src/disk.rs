@@ -0,0 +1,111 @@
+use crate::aabb::Aabb;
+use crate::hittable::{HitRecord, Hittable, SurfaceSample};
+use crate::material::MaterialId;
+use crate::math::consts::PI;
+use crate::math::Float;
+use crate::ray::Ray;
+use crate::vector::Vector;
+
+/// Flat disk; `u` is the angle around the normal and `v` the distance from the center, both in `[0, 1]`.
+pub struct Disk {
+    pub center: Vector,
+    pub normal: Vector,
+    pub radius: Float,
+    pub material: MaterialId,
+}
+
+impl Hittable for Disk {
+    fn intersect(&self, ray: &Ray, t_min: Float, t_max: Float) -> Option<HitRecord> {
+        let normal = self.normal.normalize();
+        let denominator = normal.dot(&ray.direction);
+        if denominator.abs() < 1e-12 {
+            return None;
+        }
+        let t = (self.center - ray.origin).dot(&normal) / denominator;
+        if t <= t_min || t >= t_max {
+            return None;
+        }
+
+        let point = ray.origin + ray.direction * t;
+        let local = point - self.center;
+        let distance_squared = local.length_squared();
+        if distance_squared > self.radius * self.radius {
+            return None;
+        }
+
+        let (tangent, bitangent) = normal.orthonormal_basis();
+        let phi = local.dot(&bitangent).atan2(local.dot(&tangent));
+        let (normal, front_face) = HitRecord::face_normal(ray, normal);
+        Some(HitRecord {
+            t,
+            point,
+            normal,
+            front_face,
+            u: (phi + PI) / (2.0 * PI),
+            v: distance_squared.sqrt() / self.radius,
+            material: self.material,
+            vertex_color: None,
+            tangent: None,
+        })
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        let n = self.normal.normalize();
+        let extent = Vector::new(
+            (1.0 - n.x * n.x).max(0.0).sqrt(),
+            (1.0 - n.y * n.y).max(0.0).sqrt(),
+            (1.0 - n.z * n.z).max(0.0).sqrt(),
+        ) * self.radius;
+        Aabb::new(self.center - extent, self.center + extent).padded(1e-4)
+    }
+
+    fn sample_surface(&self, u: Float, v: Float) -> SurfaceSample {
+        let normal = self.normal.normalize();
+        let (tangent, bitangent) = normal.orthonormal_basis();
+        let r = self.radius * u.sqrt();
+        let phi = 2.0 * PI * v;
+        SurfaceSample {
+            point: self.center + tangent * (r * phi.cos()) + bitangent * (r * phi.sin()),
+            normal,
+            pdf: 1.0 / (PI * self.radius * self.radius),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hit(origin: Vector, direction: Vector) -> Option<HitRecord> {
+        let disk = Disk {
+            center: Vector::new(0.0, 0.0, 1.0),
+            normal: Vector::new(0.0, 0.0, 3.0),
+            radius: 2.0,
+            material: MaterialId::default(),
+        };
+        disk.intersect(&Ray { origin, direction }, 0.0, Float::INFINITY)
+    }
+
+    #[test]
+    fn hits_with_normal_and_uv() {
+        let front = hit(Vector::new(1.0, 0.0, 5.0), Vector::new(0.0, 0.0, -1.0)).unwrap();
+        assert_eq!((front.t, front.normal, front.front_face), (4.0, Vector::new(0.0, 0.0, 1.0), true));
+        assert!((front.v - 0.5).abs() < 1e-9);
+
+        let back = hit(Vector::new(-1.0, 0.0, -1.0), Vector::new(0.0, 0.0, 1.0)).unwrap();
+        assert_eq!((back.t, back.normal, back.front_face), (2.0, Vector::new(0.0, 0.0, -1.0), false));
+        // Opposite points are half a turn apart.
+        assert!(((front.u - back.u).abs() - 0.5).abs() < 1e-9);
+
+        let center = hit(Vector::new(0.0, 0.0, 5.0), Vector::new(0.0, 0.0, -1.0)).unwrap();
+        assert_eq!(center.v, 0.0);
+        let rim = hit(Vector::new(0.0, 2.0, 5.0), Vector::new(0.0, 0.0, -1.0)).unwrap();
+        assert!((rim.v - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn misses_beyond_the_radius() {
+        assert!(hit(Vector::new(1.5, 1.5, 5.0), Vector::new(0.0, 0.0, -1.0)).is_none());
+        assert!(hit(Vector::new(0.0, 0.0, 1.0), Vector::new(1.0, 0.0, 0.0)).is_none());
+    }
+}
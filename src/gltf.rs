@@ -0,0 +1,735 @@
+//! glTF 2.0 (`.gltf` and `.glb`) import.
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use gltf::camera::Projection as GltfProjection;
+use gltf::image::Format;
+use gltf::khr_lights_punctual::Kind;
+use gltf::material::AlphaMode;
+use gltf::mesh::Mode;
+use image::{DynamicImage, RgbaImage};
+
+use crate::camera::Camera;
+use crate::hittable::Hittable;
+use crate::light::{DirectionalLight, PointLight, SpotLight};
+use crate::material::{Dissolve, Material, MaterialId, MetallicRoughness, Textured};
+use crate::math::{Float, Matrix4};
+use crate::mesh::TriangleMesh;
+use crate::scene::Scene;
+use crate::texture::ImageTexture;
+use crate::transform::Transform;
+use crate::vector::Vector;
+
+#[derive(Debug)]
+pub enum GltfError {
+    Import { path: PathBuf, source: gltf::Error },
+    InvalidMesh { path: PathBuf, mesh: String, message: String },
+    /// A feature the importer does not support; reported as a warning.
+    Unsupported { path: PathBuf, message: String },
+}
+
+impl fmt::Display for GltfError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GltfError::Import { path, source } => write!(f, "{}: {}", path.display(), source),
+            GltfError::InvalidMesh { path, mesh, message } => write!(f, "{}: mesh '{}': {}", path.display(), mesh, message),
+            GltfError::Unsupported { path, message } => write!(f, "{}: {}", path.display(), message),
+        }
+    }
+}
+
+impl std::error::Error for GltfError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            GltfError::Import { source, .. } => Some(source),
+            GltfError::InvalidMesh { .. } | GltfError::Unsupported { .. } => None,
+        }
+    }
+}
+
+/// Metallic-roughness material; textures index into `GltfScene::textures`.
+pub struct GltfMaterial {
+    pub name: Option<String>,
+    /// Linear RGBA base color factor.
+    pub base_color: [Float; 4],
+    pub base_color_texture: Option<usize>,
+    pub metallic: Float,
+    pub roughness: Float,
+    /// Metalness in the blue channel and roughness in the green channel.
+    pub metallic_roughness_texture: Option<usize>,
+    pub normal_texture: Option<usize>,
+    /// Scales the tilt of the normal texture's normals.
+    pub normal_scale: Float,
+    pub emissive: Vector,
+    pub emissive_texture: Option<usize>,
+    pub double_sided: bool,
+    pub alpha_mode: AlphaMode,
+    pub alpha_cutoff: Float,
+}
+
+impl GltfMaterial {
+    /// Adds a `MetallicRoughness` material, wrapped in `Textured` when there is a base color or
+    /// normal texture. The base color's alpha makes blended materials partly transparent and
+    /// masks masked ones through `Dissolve`; the alpha of the texture is not applied.
+    pub fn add_to_scene(&self, textures: &[RgbaImage], scene: &mut Scene) -> MaterialId {
+        let texture = |index: Option<usize>, srgb: bool| {
+            let image = textures.get(index?)?;
+            Some(ImageTexture::from_image(&DynamicImage::ImageRgba8(image.clone()), srgb))
+        };
+        let [r, g, b, alpha] = self.base_color;
+        let base_color = Vector::new(r, g, b);
+        let material = MetallicRoughness {
+            base_color,
+            metallic: self.metallic,
+            roughness: self.roughness,
+            metallic_roughness: texture(self.metallic_roughness_texture, false),
+            emissive: self.emissive,
+            emissive_texture: texture(self.emissive_texture, true),
+            double_sided: self.double_sided,
+        };
+        let color = texture(self.base_color_texture, true);
+        let normal = texture(self.normal_texture, false);
+        let material: Box<dyn Material> = if color.is_none() && normal.is_none() {
+            Box::new(material)
+        } else {
+            Box::new(Textured {
+                material: Box::new(material),
+                color,
+                tint: base_color,
+                normal,
+                normal_scale: self.normal_scale,
+                bump: None,
+                bump_scale: 1.0,
+            })
+        };
+        let opacity = match self.alpha_mode {
+            AlphaMode::Opaque => 1.0,
+            AlphaMode::Mask => if alpha >= self.alpha_cutoff { 1.0 } else { 0.0 },
+            AlphaMode::Blend => alpha.clamp(0.0, 1.0),
+        };
+        if opacity < 1.0 {
+            return scene.add_material(Dissolve { material, opacity });
+        }
+        scene.add_boxed_material(material)
+    }
+}
+
+/// One mesh primitive; several nodes may instance it.
+pub struct GltfMesh {
+    pub name: Option<String>,
+    pub mesh: TriangleMesh,
+    pub material: Option<usize>,
+}
+
+pub struct GltfInstance {
+    pub mesh: usize,
+    pub transform: Transform,
+}
+
+pub struct GltfCamera {
+    pub name: Option<String>,
+    pub eye: Vector,
+    pub look_at: Vector,
+    pub up: Vector,
+    pub yfov_degrees: Float,
+    pub aspect_ratio: Option<Float>,
+}
+
+impl GltfCamera {
+    /// Builds a perspective camera, using `aspect_ratio` when the file leaves it unspecified.
+    pub fn to_camera(&self, aspect_ratio: Float) -> Camera {
+        Camera::new(self.eye, self.look_at, self.up, self.yfov_degrees, self.aspect_ratio.unwrap_or(aspect_ratio))
+    }
+}
+
+pub enum GltfLightKind {
+    Directional,
+    Point,
+    Spot { inner_cone_angle: Float, outer_cone_angle: Float },
+}
+
+/// `KHR_lights_punctual` light in world space.
+pub struct GltfLight {
+    pub name: Option<String>,
+    pub kind: GltfLightKind,
+    pub color: Vector,
+    pub intensity: Float,
+    pub range: Option<Float>,
+    pub position: Vector,
+    pub direction: Vector,
+}
+
+impl GltfLight {
+    /// Photometric intensities are taken as radiometric ones, and `range` is ignored; loading
+    /// warns about lights that set one.
+    pub fn add_to_scene(&self, scene: &mut Scene) -> usize {
+        let intensity = self.color * self.intensity;
+        match self.kind {
+            GltfLightKind::Directional => scene.add_light(DirectionalLight {
+                direction: self.direction,
+                irradiance: intensity,
+            }),
+            GltfLightKind::Point => scene.add_light(PointLight {
+                position: self.position,
+                intensity,
+            }),
+            GltfLightKind::Spot {
+                inner_cone_angle,
+                outer_cone_angle,
+            } => scene.add_light(SpotLight {
+                position: self.position,
+                direction: self.direction,
+                intensity,
+                inner_cone_degrees: inner_cone_angle.to_degrees(),
+                outer_cone_degrees: outer_cone_angle.to_degrees(),
+            }),
+        }
+    }
+}
+
+pub struct GltfScene {
+    pub meshes: Vec<GltfMesh>,
+    pub instances: Vec<GltfInstance>,
+    pub materials: Vec<GltfMaterial>,
+    pub textures: Vec<RgbaImage>,
+    pub cameras: Vec<GltfCamera>,
+    pub lights: Vec<GltfLight>,
+    /// Features in the file that the import leaves out, such as orthographic cameras.
+    pub warnings: Vec<GltfError>,
+}
+
+impl GltfScene {
+    /// Adds the materials and lights, then one instance per mesh placement; instances share
+    /// the mesh geometry.
+    pub fn add_to_scene(self, scene: &mut Scene) {
+        let ids = self.materials.iter().map(|material| material.add_to_scene(&self.textures, scene)).collect::<Vec<MaterialId>>();
+        for light in &self.lights {
+            light.add_to_scene(scene);
+        }
+        let meshes = self
+            .meshes
+            .into_iter()
+            .map(|mut mesh| {
+                if let Some(material) = mesh.material {
+                    mesh.mesh.material = ids[material];
+                }
+                Arc::new(mesh.mesh) as Arc<dyn Hittable>
+            })
+            .collect::<Vec<Arc<dyn Hittable>>>();
+        for instance in self.instances {
+            scene.add_instance(meshes[instance.mesh].clone(), instance.transform);
+        }
+    }
+}
+
+pub fn load_gltf(path: impl AsRef<Path>) -> Result<GltfScene, GltfError> {
+    let path = path.as_ref();
+    let (document, buffers, images) = gltf::import(path).map_err(|source| GltfError::Import {
+        path: path.to_path_buf(),
+        source,
+    })?;
+
+    let mut warnings = Vec::new();
+    let textures = images.iter().map(convert_image).collect::<Vec<RgbaImage>>();
+    let texture_of = |info: Option<gltf::texture::Info>| info.map(|info| info.texture().source().index());
+    let materials = document
+        .materials()
+        .map(|material| {
+            let pbr = material.pbr_metallic_roughness();
+            let [r, g, b, a] = pbr.base_color_factor();
+            let [er, eg, eb] = material.emissive_factor();
+            if material.alpha_mode() != AlphaMode::Opaque && pbr.base_color_texture().is_some() {
+                let message = "the base color texture's alpha is not applied";
+                warnings.push(unsupported(path, "material", material.name(), material.index().unwrap_or_default(), message));
+            }
+            GltfMaterial {
+                name: material.name().map(str::to_string),
+                base_color: [r as Float, g as Float, b as Float, a as Float],
+                base_color_texture: texture_of(pbr.base_color_texture()),
+                metallic: pbr.metallic_factor() as Float,
+                roughness: pbr.roughness_factor() as Float,
+                metallic_roughness_texture: texture_of(pbr.metallic_roughness_texture()),
+                normal_texture: material.normal_texture().map(|normal| normal.texture().source().index()),
+                normal_scale: material.normal_texture().map_or(1.0, |normal| normal.scale() as Float),
+                emissive: Vector::new(er as Float, eg as Float, eb as Float),
+                emissive_texture: texture_of(material.emissive_texture()),
+                double_sided: material.double_sided(),
+                alpha_mode: material.alpha_mode(),
+                alpha_cutoff: material.alpha_cutoff().unwrap_or(0.5) as Float,
+            }
+        })
+        .collect::<Vec<GltfMaterial>>();
+
+    // Primitives are flattened, so remember where each glTF mesh's primitives start.
+    let mut meshes = Vec::new();
+    let mut primitive_ranges = Vec::new();
+    for mesh in document.meshes() {
+        let start = meshes.len();
+        for primitive in mesh.primitives() {
+            let invalid = |message: &str| GltfError::InvalidMesh {
+                path: path.to_path_buf(),
+                mesh: mesh.name().map_or_else(|| mesh.index().to_string(), str::to_string),
+                message: message.to_string(),
+            };
+            let reader = primitive.reader(|buffer| Some(&buffers[buffer.index()]));
+            let positions = reader
+                .read_positions()
+                .ok_or_else(|| invalid("primitive has no POSITION attribute"))?
+                .map(|p| Vector::new(p[0] as Float, p[1] as Float, p[2] as Float))
+                .collect::<Vec<Vector>>();
+            let vertex_indices = match reader.read_indices() {
+                Some(indices) => indices.into_u32().collect::<Vec<u32>>(),
+                None => (0..positions.len() as u32).collect(),
+            };
+            let indices = match primitive.mode() {
+                Mode::Triangles => vertex_indices.chunks_exact(3).map(|c| [c[0], c[1], c[2]]).collect(),
+                Mode::TriangleStrip => (2..vertex_indices.len())
+                    .map(|i| {
+                        if i % 2 == 0 {
+                            [vertex_indices[i - 2], vertex_indices[i - 1], vertex_indices[i]]
+                        } else {
+                            [vertex_indices[i - 1], vertex_indices[i - 2], vertex_indices[i]]
+                        }
+                    })
+                    .collect(),
+                Mode::TriangleFan => (2..vertex_indices.len()).map(|i| [vertex_indices[0], vertex_indices[i - 1], vertex_indices[i]]).collect(),
+                // Points and lines have no surface to trace.
+                _ => continue,
+            };
+
+            let material_index = primitive.material().index();
+            let base_color = material_index.map_or(Vector::splat(1.0), |index| {
+                let [r, g, b, _] = materials[index].base_color;
+                Vector::new(r, g, b)
+            });
+            let mut triangle_mesh = TriangleMesh::new(positions, indices, MaterialId::default());
+            triangle_mesh.normals = reader
+                .read_normals()
+                .map(|normals| normals.map(|n| Vector::new(n[0] as Float, n[1] as Float, n[2] as Float)).collect());
+            // COLOR_0 multiplies the base color factor.
+            triangle_mesh.colors = reader
+                .read_colors(0)
+                .map(|colors| colors.into_rgb_f32().map(|c| Vector::new(c[0] as Float, c[1] as Float, c[2] as Float) * base_color).collect());
+            // glTF puts v = 0 at the top of an image, and `ImageTexture` at the bottom.
+            triangle_mesh.uvs = reader.read_tex_coords(0).map(|uvs| uvs.into_f32().map(|uv| [uv[0] as Float, 1.0 - uv[1] as Float]).collect());
+            // TANGENT's w is the handedness that mirrored UVs need for the bitangent.
+            if let Some(tangents) = reader.read_tangents() {
+                let (tangents, signs) = tangents
+                    .map(|t| (Vector::new(t[0] as Float, t[1] as Float, t[2] as Float), if t[3] < 0.0 { -1.0 } else { 1.0 }))
+                    .unzip();
+                triangle_mesh.tangents = Some(tangents);
+                triangle_mesh.bitangent_signs = Some(signs);
+            }
+            triangle_mesh.validate().map_err(|error| invalid(&error.to_string()))?;
+            // Without NORMAL the spec asks for flat shading, which the geometric normal gives.
+            if triangle_mesh.tangents.is_none() {
+                triangle_mesh.compute_tangents();
+            }
+
+            meshes.push(GltfMesh {
+                name: mesh.name().map(str::to_string),
+                mesh: triangle_mesh,
+                material: material_index,
+            });
+        }
+        primitive_ranges.push(start..meshes.len());
+    }
+
+    let mut scene = GltfScene {
+        meshes,
+        instances: Vec::new(),
+        materials,
+        textures,
+        cameras: Vec::new(),
+        lights: Vec::new(),
+        warnings,
+    };
+    let root = document.default_scene().or_else(|| document.scenes().next());
+    for node in root.iter().flat_map(|root| root.nodes()) {
+        visit_node(path, &node, Matrix4::from_scale(1.0), &primitive_ranges, &mut scene);
+    }
+    Ok(scene)
+}
+
+fn visit_node(path: &Path, node: &gltf::Node, parent: Matrix4, primitive_ranges: &[std::ops::Range<usize>], scene: &mut GltfScene) {
+    let local = node.transform().matrix();
+    let local = Matrix4::from(local.map(|column| column.map(|value| value as Float)));
+    let world = parent * local;
+    // A singular matrix, such as a zero scale, hides the whole subtree: every descendant's
+    // matrix has it as a factor and so is singular too.
+    let Some(transform) = Transform::from_matrix(world) else {
+        return;
+    };
+    add_node_contents(path, node, transform, primitive_ranges, scene);
+    for child in node.children() {
+        visit_node(path, &child, world, primitive_ranges, scene);
+    }
+}
+
+fn add_node_contents(path: &Path, node: &gltf::Node, transform: Transform, primitive_ranges: &[std::ops::Range<usize>], scene: &mut GltfScene) {
+    if let Some(mesh) = node.mesh() {
+        for index in primitive_ranges[mesh.index()].clone() {
+            scene.instances.push(GltfInstance { mesh: index, transform });
+        }
+    }
+
+    // Cameras and lights look down their local -z axis.
+    let position = transform.transform_point(Vector::zero());
+    let forward = transform.transform_vector(Vector::new(0.0, 0.0, -1.0)).normalize();
+    if let Some(camera) = node.camera() {
+        match camera.projection() {
+            GltfProjection::Perspective(perspective) => scene.cameras.push(GltfCamera {
+                name: camera.name().map(str::to_string),
+                eye: position,
+                look_at: position + forward,
+                up: transform.transform_vector(Vector::new(0.0, 1.0, 0.0)).normalize(),
+                yfov_degrees: (perspective.yfov() as Float).to_degrees(),
+                aspect_ratio: perspective.aspect_ratio().map(|aspect| aspect as Float),
+            }),
+            GltfProjection::Orthographic(_) => {
+                let warning = unsupported(path, "camera", camera.name(), camera.index(), "orthographic projection is not supported");
+                scene.warnings.push(warning);
+            }
+        }
+    }
+    if let Some(light) = node.light() {
+        if light.range().is_some() {
+            scene.warnings.push(unsupported(path, "light", light.name(), light.index(), "range is ignored"));
+        }
+        let [r, g, b] = light.color();
+        scene.lights.push(GltfLight {
+            name: light.name().map(str::to_string),
+            kind: match light.kind() {
+                Kind::Directional => GltfLightKind::Directional,
+                Kind::Point => GltfLightKind::Point,
+                Kind::Spot {
+                    inner_cone_angle,
+                    outer_cone_angle,
+                } => GltfLightKind::Spot {
+                    inner_cone_angle: inner_cone_angle as Float,
+                    outer_cone_angle: outer_cone_angle as Float,
+                },
+            },
+            color: Vector::new(r as Float, g as Float, b as Float),
+            intensity: light.intensity() as Float,
+            range: light.range().map(|range| range as Float),
+            position,
+            direction: forward,
+        });
+    }
+}
+
+/// Warning about a feature of the named (or else numbered) element that is left out.
+fn unsupported(path: &Path, kind: &str, name: Option<&str>, index: usize, message: &str) -> GltfError {
+    GltfError::Unsupported {
+        path: path.to_path_buf(),
+        message: format!("{kind} '{}': {message}", name.map_or_else(|| index.to_string(), str::to_string)),
+    }
+}
+
+fn convert_image(data: &gltf::image::Data) -> RgbaImage {
+    let pixel_count = (data.width * data.height) as usize;
+    let channels = match data.format {
+        Format::R8 | Format::R16 => 1,
+        Format::R8G8 | Format::R16G16 => 2,
+        Format::R8G8B8 | Format::R16G16B16 | Format::R32G32B32FLOAT => 3,
+        Format::R8G8B8A8 | Format::R16G16B16A16 | Format::R32G32B32A32FLOAT => 4,
+    };
+    let bytes_per_channel = data.pixels.len() / (pixel_count * channels).max(1);
+    let channel = |pixel: usize, c: usize| -> u8 {
+        let offset = (pixel * channels + c) * bytes_per_channel;
+        match data.format {
+            Format::R32G32B32FLOAT | Format::R32G32B32A32FLOAT => {
+                let bytes = [data.pixels[offset], data.pixels[offset + 1], data.pixels[offset + 2], data.pixels[offset + 3]];
+                (f32::from_le_bytes(bytes).clamp(0.0, 1.0) * 255.0).round() as u8
+            }
+            Format::R16 | Format::R16G16 | Format::R16G16B16 | Format::R16G16B16A16 => {
+                (u16::from_le_bytes([data.pixels[offset], data.pixels[offset + 1]]) >> 8) as u8
+            }
+            _ => data.pixels[offset],
+        }
+    };
+
+    let mut rgba = Vec::with_capacity(pixel_count * 4);
+    for pixel in 0..pixel_count {
+        let values = match channels {
+            1 => {
+                let value = channel(pixel, 0);
+                [value, value, value, 255]
+            }
+            2 => {
+                let value = channel(pixel, 0);
+                [value, value, value, channel(pixel, 1)]
+            }
+            3 => [channel(pixel, 0), channel(pixel, 1), channel(pixel, 2), 255],
+            _ => [channel(pixel, 0), channel(pixel, 1), channel(pixel, 2), channel(pixel, 3)],
+        };
+        rgba.extend_from_slice(&values);
+    }
+    RgbaImage::from_raw(data.width, data.height, rgba).unwrap_or_else(|| RgbaImage::new(data.width, data.height))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ray::Ray;
+
+    /// One triangle facing +z with an embedded buffer and an external 1x2 texture; every
+    /// vertex has UV (0.5, 0.25), the top texel in glTF's convention.
+    const TRIANGLE: &str = r#"{
+        "asset": { "version": "2.0" },
+        "buffers": [{
+            "byteLength": 60,
+            "uri": "data:application/octet-stream;base64,AACAvwAAgL8AAAAAAACAPwAAgL8AAAAAAAAAAAAAgD8AAAAAAAAAPwAAgD4AAAA/AACAPgAAAD8AAIA+"
+        }],
+        "bufferViews": [
+            { "buffer": 0, "byteOffset": 0, "byteLength": 36 },
+            { "buffer": 0, "byteOffset": 36, "byteLength": 24 }
+        ],
+        "accessors": [
+            { "bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3", "min": [-1, -1, 0], "max": [1, 1, 0] },
+            { "bufferView": 1, "componentType": 5126, "count": 3, "type": "VEC2" }
+        ],
+        "images": [{ "uri": "color.png" }],
+        "textures": [{ "source": 0 }],
+        "materials": [{
+            "pbrMetallicRoughness": { "baseColorTexture": { "index": 0 }, "metallicFactor": 0.0, "roughnessFactor": 1.0 },
+            "emissiveFactor": [1.0, 0.5, 0.0],
+            "doubleSided": true
+        }],
+        "meshes": [{ "primitives": [{ "attributes": { "POSITION": 0, "TEXCOORD_0": 1 }, "material": 0 }] }],
+        "nodes": [{ "mesh": 0 }],
+        "scenes": [{ "nodes": [0] }],
+        "scene": 0
+    }"#;
+
+    /// `TRIANGLE`'s buffer and position accessor, for documents that need only geometry.
+    const TRIANGLE_GEOMETRY: &str = r#"
+        "asset": { "version": "2.0" },
+        "buffers": [{
+            "byteLength": 60,
+            "uri": "data:application/octet-stream;base64,AACAvwAAgL8AAAAAAACAPwAAgL8AAAAAAAAAAAAAgD8AAAAAAAAAPwAAgD4AAAA/AACAPgAAAD8AAIA+"
+        }],
+        "bufferViews": [{ "buffer": 0, "byteOffset": 0, "byteLength": 36 }],
+        "accessors": [{ "bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3", "min": [-1, -1, 0], "max": [1, 1, 0] }],
+        "meshes": [{ "primitives": [{ "attributes": { "POSITION": 0 } }] }]
+    "#;
+
+    /// Loads a document whose buffers are all embedded.
+    fn load_document(name: &str, document: &str) -> Result<GltfScene, GltfError> {
+        let path = std::env::temp_dir().join(format!("raytrace-gltf-{name}-{}.gltf", std::process::id()));
+        std::fs::write(&path, document).unwrap();
+        let loaded = load_gltf(&path);
+        std::fs::remove_file(&path).unwrap();
+        loaded
+    }
+
+    #[test]
+    fn singular_nodes_hide_their_subtree() {
+        // The child's scale cannot undo its parent's zero scale.
+        let nodes = r#""nodes": [{ "scale": [0, 0, 0], "children": [1] }, { "mesh": 0, "scale": [1000, 1000, 1000] }, { "mesh": 0 }],
+            "scenes": [{ "nodes": [0, 2] }]"#;
+        let loaded = load_document("singular", &format!("{{{TRIANGLE_GEOMETRY}, {nodes}}}")).unwrap();
+        assert_eq!(loaded.instances.len(), 1);
+    }
+
+    #[test]
+    fn loads_embedded_buffer_and_external_texture() {
+        let directory = std::env::temp_dir().join(format!("raytrace-gltf-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        image::RgbImage::from_raw(1, 2, vec![255, 0, 0, 0, 255, 0]).unwrap().save(directory.join("color.png")).unwrap();
+        std::fs::write(directory.join("triangle.gltf"), TRIANGLE).unwrap();
+        let loaded = load_gltf(directory.join("triangle.gltf"));
+        std::fs::remove_dir_all(&directory).unwrap();
+        let loaded = loaded.unwrap();
+
+        assert_eq!(loaded.meshes.len(), 1);
+        assert_eq!(loaded.instances.len(), 1);
+        assert_eq!(loaded.textures.len(), 1);
+        let material = &loaded.materials[0];
+        assert_eq!(material.base_color_texture, Some(0));
+        assert!(material.double_sided);
+        assert_eq!(loaded.meshes[0].mesh.uvs.as_ref().unwrap()[0], [0.5, 0.75]);
+
+        let mut scene = Scene::new();
+        loaded.add_to_scene(&mut scene);
+        let emitted = |origin: Vector, direction: Vector| {
+            let ray = Ray { origin, direction };
+            let hit = scene.intersect(&ray, 0.0, Float::INFINITY).unwrap();
+            scene.material(hit.material).emitted(&ray, &hit)
+        };
+        // Emission is kept on top of the base material, and both faces emit.
+        assert_eq!(emitted(Vector::new(0.0, 0.0, 3.0), Vector::new(0.0, 0.0, -1.0)), Vector::new(1.0, 0.5, 0.0));
+        assert_eq!(emitted(Vector::new(0.0, 0.0, -3.0), Vector::new(0.0, 0.0, 1.0)), Vector::new(1.0, 0.5, 0.0));
+
+        // The base color comes from the red top texel.
+        let ray = Ray {
+            origin: Vector::new(0.0, 0.0, 3.0),
+            direction: Vector::new(0.0, 0.0, -1.0),
+        };
+        let hit = scene.intersect(&ray, 0.0, Float::INFINITY).unwrap();
+        let up = Vector::new(0.0, 0.0, 1.0);
+        let reflected = scene.material(hit.material).evaluate(&hit, &up, &up);
+        assert!(reflected.x > 0.1 && reflected.x > 10.0 * reflected.y, "{reflected:?}");
+    }
+
+    #[test]
+    fn meshes_without_normals_are_flat_shaded() {
+        // Two triangles folded along the x axis, sharing that edge, in an external buffer.
+        let positions: [[f32; 3]; 4] = [[-1.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 1.0], [0.0, -1.0, 1.0]];
+        let indices: [u16; 6] = [0, 1, 2, 1, 0, 3];
+        let mut buffer = positions.iter().flatten().flat_map(|value| value.to_le_bytes()).collect::<Vec<u8>>();
+        buffer.extend(indices.iter().flat_map(|index| index.to_le_bytes()));
+        let document = r#"{
+            "asset": { "version": "2.0" },
+            "buffers": [{ "byteLength": 60, "uri": "folded.bin" }],
+            "bufferViews": [
+                { "buffer": 0, "byteOffset": 0, "byteLength": 48 },
+                { "buffer": 0, "byteOffset": 48, "byteLength": 12 }
+            ],
+            "accessors": [
+                { "bufferView": 0, "componentType": 5126, "count": 4, "type": "VEC3", "min": [-1, -1, 0], "max": [1, 1, 1] },
+                { "bufferView": 1, "componentType": 5123, "count": 6, "type": "SCALAR" }
+            ],
+            "meshes": [{ "primitives": [{ "attributes": { "POSITION": 0 }, "indices": 1 }] }],
+            "nodes": [{ "mesh": 0 }],
+            "scenes": [{ "nodes": [0] }]
+        }"#;
+        let directory = std::env::temp_dir().join(format!("raytrace-gltf-flat-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        std::fs::write(directory.join("folded.bin"), buffer).unwrap();
+        std::fs::write(directory.join("folded.gltf"), document).unwrap();
+        let loaded = load_gltf(directory.join("folded.gltf"));
+        std::fs::remove_dir_all(&directory).unwrap();
+        let loaded = loaded.unwrap();
+        assert!(loaded.meshes[0].mesh.normals.is_none());
+
+        // Close to the shared edge, smooth normals would lean towards +z.
+        let mut scene = Scene::new();
+        loaded.add_to_scene(&mut scene);
+        let ray = Ray {
+            origin: Vector::new(0.0, 0.1, 3.0),
+            direction: Vector::new(0.0, 0.0, -1.0),
+        };
+        let hit = scene.intersect(&ray, 0.0, Float::INFINITY).unwrap();
+        assert!((hit.normal - Vector::new(0.0, -1.0, 1.0).normalize()).length() < 1e-5, "{:?}", hit.normal);
+    }
+
+    #[test]
+    fn warns_about_unsupported_features() {
+        let document = r#"{
+            "asset": { "version": "2.0" },
+            "extensionsUsed": ["KHR_lights_punctual"],
+            "extensions": { "KHR_lights_punctual": { "lights": [{ "type": "point", "range": 10 }] } },
+            "buffers": [{
+                "byteLength": 60,
+                "uri": "data:application/octet-stream;base64,AACAvwAAgL8AAAAAAACAPwAAgL8AAAAAAAAAAAAAgD8AAAAAAAAAPwAAgD4AAAA/AACAPgAAAD8AAIA+"
+            }],
+            "bufferViews": [
+                { "buffer": 0, "byteOffset": 0, "byteLength": 36 },
+                { "buffer": 0, "byteOffset": 36, "byteLength": 24 }
+            ],
+            "accessors": [
+                { "bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3", "min": [-1, -1, 0], "max": [1, 1, 0] },
+                { "bufferView": 1, "componentType": 5126, "count": 3, "type": "VEC2" }
+            ],
+            "images": [{ "uri": "color.png" }],
+            "textures": [{ "source": 0 }],
+            "materials": [{
+                "name": "glaze",
+                "pbrMetallicRoughness": { "baseColorFactor": [1, 1, 1, 0.25], "baseColorTexture": { "index": 0 } },
+                "alphaMode": "BLEND"
+            }],
+            "meshes": [{ "primitives": [{ "attributes": { "POSITION": 0, "TEXCOORD_0": 1 }, "material": 0 }] }],
+            "cameras": [{ "type": "orthographic", "orthographic": { "xmag": 1, "ymag": 1, "zfar": 100, "znear": 0.1 } }],
+            "nodes": [{ "mesh": 0 }, { "camera": 0 }, { "extensions": { "KHR_lights_punctual": { "light": 0 } } }],
+            "scenes": [{ "nodes": [0, 1, 2] }]
+        }"#;
+        let directory = std::env::temp_dir().join(format!("raytrace-gltf-warnings-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        image::RgbImage::from_raw(1, 1, vec![255, 255, 255]).unwrap().save(directory.join("color.png")).unwrap();
+        std::fs::write(directory.join("warnings.gltf"), document).unwrap();
+        let loaded = load_gltf(directory.join("warnings.gltf"));
+        std::fs::remove_dir_all(&directory).unwrap();
+        let loaded = loaded.unwrap();
+
+        let warnings = loaded.warnings.iter().map(|warning| warning.to_string()).collect::<Vec<String>>();
+        let path = directory.join("warnings.gltf").display().to_string();
+        assert_eq!(
+            warnings,
+            [
+                format!("{path}: material 'glaze': the base color texture's alpha is not applied"),
+                format!("{path}: camera '0': orthographic projection is not supported"),
+                format!("{path}: light '0': range is ignored"),
+            ]
+        );
+        assert!(loaded.cameras.is_empty());
+        assert_eq!(loaded.lights.len(), 1);
+
+        // The alpha factor still lets three quarters of the light through.
+        let mut scene = Scene::new();
+        loaded.add_to_scene(&mut scene);
+        let ray = Ray {
+            origin: Vector::new(0.0, 0.0, 3.0),
+            direction: Vector::new(0.0, 0.0, -1.0),
+        };
+        let hit = scene.intersect(&ray, 0.0, Float::INFINITY).unwrap();
+        let specular = scene.material(hit.material).specular(&ray, &hit);
+        assert_eq!((specular.transmittance, specular.refracted), (Vector::splat(0.75), Some(ray.direction)));
+    }
+
+    #[test]
+    fn normal_textures_tilt_the_shading_normal() {
+        // A triangle facing +z whose u runs along +x, with a normal texture tilted towards -u.
+        let positions: [[f32; 3]; 3] = [[-1.0, -1.0, 0.0], [1.0, -1.0, 0.0], [0.0, 1.0, 0.0]];
+        let uvs: [[f32; 2]; 3] = [[0.0, 1.0], [1.0, 1.0], [0.5, 0.0]];
+        let mut buffer = positions.iter().flatten().flat_map(|value| value.to_le_bytes()).collect::<Vec<u8>>();
+        buffer.extend(uvs.iter().flatten().flat_map(|value| value.to_le_bytes()));
+        let document = r#"{
+            "asset": { "version": "2.0" },
+            "buffers": [{ "byteLength": 60, "uri": "tilted.bin" }],
+            "bufferViews": [
+                { "buffer": 0, "byteOffset": 0, "byteLength": 36 },
+                { "buffer": 0, "byteOffset": 36, "byteLength": 24 }
+            ],
+            "accessors": [
+                { "bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3", "min": [-1, -1, 0], "max": [1, 1, 0] },
+                { "bufferView": 1, "componentType": 5126, "count": 3, "type": "VEC2" }
+            ],
+            "images": [{ "uri": "normal.png" }],
+            "textures": [{ "source": 0 }],
+            "materials": [{ "pbrMetallicRoughness": { "metallicFactor": 0.0 }, "normalTexture": { "index": 0, "scale": 0.5 } }],
+            "meshes": [{ "primitives": [{ "attributes": { "POSITION": 0, "TEXCOORD_0": 1 }, "material": 0 }] }],
+            "nodes": [{ "mesh": 0 }],
+            "scenes": [{ "nodes": [0] }]
+        }"#;
+        let directory = std::env::temp_dir().join(format!("raytrace-gltf-normal-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        std::fs::write(directory.join("tilted.bin"), buffer).unwrap();
+        // (-1, 0, 1) in tangent space, which the scale of 1/2 turns into (-1, 0, 2).
+        image::RgbImage::from_raw(1, 1, vec![0, 128, 255]).unwrap().save(directory.join("normal.png")).unwrap();
+        std::fs::write(directory.join("tilted.gltf"), document).unwrap();
+        let loaded = load_gltf(directory.join("tilted.gltf"));
+        std::fs::remove_dir_all(&directory).unwrap();
+        let loaded = loaded.unwrap();
+        assert_eq!((loaded.materials[0].normal_texture, loaded.materials[0].normal_scale), (Some(0), 0.5));
+
+        let mut scene = Scene::new();
+        loaded.add_to_scene(&mut scene);
+        let ray = Ray {
+            origin: Vector::new(0.0, 0.0, 3.0),
+            direction: Vector::new(0.0, 0.0, -1.0),
+        };
+        let hit = scene.intersect(&ray, 0.0, Float::INFINITY).unwrap();
+        let material = scene.material(hit.material);
+        let up = Vector::new(0.0, 0.0, 1.0);
+        // Light arriving square to the shading normal (-1, 0, 2) is reflected, and light
+        // from below its horizon is not.
+        let facing = material.evaluate(&hit, &up, &Vector::new(-1.0, 0.0, 2.0).normalize());
+        let grazing = material.evaluate(&hit, &up, &Vector::new(2.0, 0.0, 0.9).normalize());
+        assert!(facing.x > 0.1, "{facing:?}");
+        assert!(grazing.x < 1e-3, "{grazing:?}");
+    }
+}
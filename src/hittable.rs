@@ -0,0 +1,72 @@
+use std::sync::Arc;
+
+use crate::aabb::Aabb;
+use crate::material::MaterialId;
+use crate::math::Float;
+use crate::ray::Ray;
+use crate::vector::Vector;
+
+#[derive(Copy, Clone)]
+pub struct HitRecord {
+    pub t: Float,
+    pub point: Vector,
+    pub normal: Vector,
+    pub front_face: bool,
+    pub u: Float,
+    pub v: Float,
+    pub material: MaterialId,
+    /// Interpolated vertex color, which materials use in place of their base color.
+    pub vertex_color: Option<Vector>,
+    /// Unit surface tangent and bitangent along increasing `u` and `v`, when the shape provides
+    /// them. Like `normal`, both are negated on back faces.
+    pub tangent: Option<(Vector, Vector)>,
+}
+
+impl HitRecord {
+    /// Orients `outward_normal` against the ray and reports which side was hit.
+    pub fn face_normal(ray: &Ray, outward_normal: Vector) -> (Vector, bool) {
+        let front_face = ray.direction.dot(&outward_normal) < 0.0;
+        (if front_face { outward_normal } else { -outward_normal }, front_face)
+    }
+}
+
+pub struct SurfaceSample {
+    pub point: Vector,
+    pub normal: Vector,
+    pub pdf: Float,
+}
+
+/// Rays passed to `intersect` may have non-unit directions, e.g. after an instance transform,
+/// and `t` is measured in multiples of the direction.
+pub trait Hittable: Send + Sync {
+    fn intersect(&self, ray: &Ray, t_min: Float, t_max: Float) -> Option<HitRecord>;
+
+    /// Whether anything is hit in `(t_min, t_max)`; shapes can override it to stop at the first hit.
+    fn occluded(&self, ray: &Ray, t_min: Float, t_max: Float) -> bool {
+        self.intersect(ray, t_min, t_max).is_some()
+    }
+
+    fn bounding_box(&self) -> Aabb;
+
+    /// Maps `(u, v)` in `[0, 1)^2` to a point on the surface; `pdf` is with respect to area.
+    fn sample_surface(&self, u: Float, v: Float) -> SurfaceSample;
+}
+
+/// Lets one shape sit in a scene and be referenced elsewhere, such as by an `AreaLight`.
+impl<T: Hittable + ?Sized> Hittable for Arc<T> {
+    fn intersect(&self, ray: &Ray, t_min: Float, t_max: Float) -> Option<HitRecord> {
+        (**self).intersect(ray, t_min, t_max)
+    }
+
+    fn occluded(&self, ray: &Ray, t_min: Float, t_max: Float) -> bool {
+        (**self).occluded(ray, t_min, t_max)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        (**self).bounding_box()
+    }
+
+    fn sample_surface(&self, u: Float, v: Float) -> SurfaceSample {
+        (**self).sample_surface(u, v)
+    }
+}
@@ -0,0 +1,123 @@
+use std::sync::Arc;
+
+use crate::aabb::Aabb;
+use crate::hittable::{HitRecord, Hittable, SurfaceSample};
+use crate::math::Float;
+use crate::ray::Ray;
+use crate::transform::Transform;
+
+/// Places a shared object in the scene; many instances may reference the same geometry.
+pub struct Instance {
+    object: Arc<dyn Hittable>,
+    transform: Transform,
+    bounds: Aabb,
+}
+
+impl Instance {
+    pub fn new(object: Arc<dyn Hittable>, transform: Transform) -> Instance {
+        let bounds = transform.transform_aabb(&object.bounding_box());
+        Instance { object, transform, bounds }
+    }
+
+    pub fn object(&self) -> &Arc<dyn Hittable> {
+        &self.object
+    }
+
+    pub fn transform(&self) -> &Transform {
+        &self.transform
+    }
+
+    pub fn set_transform(&mut self, transform: Transform) {
+        self.transform = transform;
+        self.bounds = transform.transform_aabb(&self.object.bounding_box());
+    }
+}
+
+impl Hittable for Instance {
+    fn intersect(&self, ray: &Ray, t_min: Float, t_max: Float) -> Option<HitRecord> {
+        let local_ray = self.transform.inverse().transform_ray(ray);
+        let mut hit = self.object.intersect(&local_ray, t_min, t_max)?;
+        hit.point = self.transform.transform_point(hit.point);
+        hit.normal = self.transform.transform_normal(hit.normal).normalize();
+        hit.tangent = hit.tangent.map(|(tangent, bitangent)| {
+            (self.transform.transform_vector(tangent).normalize(), self.transform.transform_vector(bitangent).normalize())
+        });
+        Some(hit)
+    }
+
+    fn occluded(&self, ray: &Ray, t_min: Float, t_max: Float) -> bool {
+        self.object.occluded(&self.transform.inverse().transform_ray(ray), t_min, t_max)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bounds
+    }
+
+    fn sample_surface(&self, u: Float, v: Float) -> SurfaceSample {
+        let sample = self.object.sample_surface(u, v);
+        SurfaceSample {
+            point: self.transform.transform_point(sample.point),
+            normal: self.transform.transform_normal(sample.normal).normalize(),
+            pdf: sample.pdf / self.transform.area_scale(sample.normal),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::MaterialId;
+    use crate::math::consts::PI;
+    use crate::sphere::Sphere;
+    use crate::vector::Vector;
+
+    fn assert_close(actual: Vector, expected: Vector) {
+        assert!((actual - expected).length() < 1e-5, "{actual:?} != {expected:?}");
+    }
+
+    /// A unit sphere stretched to radii (2, 2, 3) and centred at (0, 0, -5).
+    fn ellipsoid() -> Instance {
+        let sphere = Sphere {
+            center: Vector::zero(),
+            radius: 1.0,
+            material: MaterialId::default(),
+        };
+        let transform = Transform::translate(Vector::new(0.0, 0.0, -5.0)) * Transform::scale(Vector::new(2.0, 2.0, 3.0)).unwrap();
+        Instance::new(Arc::new(sphere), transform)
+    }
+
+    #[test]
+    fn hits_in_world_space() {
+        let instance = ellipsoid();
+        let top = Ray {
+            origin: Vector::zero(),
+            direction: Vector::new(0.0, 0.0, -1.0),
+        };
+        let hit = instance.intersect(&top, 0.0, Float::INFINITY).unwrap();
+        assert!((hit.t - 2.0).abs() < 1e-6, "{}", hit.t);
+        assert_close(hit.point, Vector::new(0.0, 0.0, -2.0));
+        assert_close(hit.normal, Vector::new(0.0, 0.0, 1.0));
+
+        let side = Ray {
+            origin: Vector::new(10.0, 0.0, -5.0),
+            direction: Vector::new(-1.0, 0.0, 0.0),
+        };
+        let hit = instance.intersect(&side, 0.0, Float::INFINITY).unwrap();
+        assert!((hit.t - 8.0).abs() < 1e-6, "{}", hit.t);
+        assert_close(hit.normal, Vector::new(1.0, 0.0, 0.0));
+        assert_eq!(instance.bounding_box().min, Vector::new(-2.0, -2.0, -8.0));
+    }
+
+    #[test]
+    fn sample_pdf_follows_the_stretched_area() {
+        let instance = ellipsoid();
+        // The pole is scaled by 2 x 2 and a point on the equator by 2 x 3.
+        let pole = instance.sample_surface(0.0, 0.0);
+        assert_close(pole.point, Vector::new(0.0, 0.0, -2.0));
+        assert!((pole.pdf - 1.0 / (16.0 * PI)).abs() < 1e-6, "{}", pole.pdf);
+        let equator = instance.sample_surface(0.5, 0.0);
+        assert_close(equator.point, Vector::new(2.0, 0.0, -5.0));
+        assert_close(equator.normal, Vector::new(1.0, 0.0, 0.0));
+        assert!((equator.pdf - 1.0 / (24.0 * PI)).abs() < 1e-6, "{}", equator.pdf);
+    }
+}
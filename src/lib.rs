@@ -0,0 +1,30 @@
+pub mod aabb;
+pub mod bvh;
+pub mod camera;
+pub mod cuboid;
+pub mod disk;
+pub mod gltf;
+pub mod hittable;
+pub mod instance;
+pub mod light;
+pub mod material;
+pub mod math;
+pub mod mesh;
+pub mod obj;
+pub mod plane;
+pub mod ply;
+pub mod quad;
+pub mod quadric;
+pub mod ray;
+pub mod render;
+pub mod scene;
+pub mod solver;
+pub mod sphere;
+pub mod stereo;
+pub mod stl;
+pub mod texture;
+pub mod tlas;
+pub mod torus;
+pub mod transform;
+pub mod vector;
+pub mod wide_bvh;
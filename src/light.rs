@@ -0,0 +1,295 @@
+//! Light sources sampled for direct lighting. A shading point asks each light for a direction
+//! and the light it would receive from there, then casts a shadow ray to check visibility.
+
+use std::sync::Arc;
+
+use rand::{Rng, RngCore};
+
+use crate::hittable::Hittable;
+use crate::math::Float;
+use crate::ray::Ray;
+use crate::vector::Vector;
+
+/// Light arriving at a point from one sample of a light.
+pub struct LightSample {
+    /// Unit direction from the shading point toward the light.
+    pub direction: Vector,
+    /// Distance to the sampled point on the light; infinite for directional lights.
+    pub distance: Float,
+    /// Irradiance on a surface facing `direction`, divided by the solid-angle density of the
+    /// sample for area lights.
+    pub irradiance: Vector,
+}
+
+/// Distance below which a shading point counts as sitting on a point or spot light, which then
+/// gives no finite direction or irradiance. Area lights skip such samples too.
+const MIN_DISTANCE: Float = 1e-6;
+
+pub trait Light: Send + Sync {
+    /// Samples the light as seen from `point`, or `None` if it sends no light there.
+    fn sample(&self, point: &Vector, rng: &mut dyn RngCore) -> Option<LightSample>;
+
+    /// Whether the closest hit of `ray`, at `t`, lies on this light. Emission found there by a
+    /// scattered ray was already counted by `sample`. Lights without a surface are never hit.
+    fn is_hit(&self, _ray: &Ray, _t: Float) -> bool {
+        false
+    }
+}
+
+/// Light from a single direction, like the sun.
+pub struct DirectionalLight {
+    /// Direction the light travels in.
+    pub direction: Vector,
+    /// Irradiance on a surface facing the light.
+    pub irradiance: Vector,
+}
+
+impl Light for DirectionalLight {
+    fn sample(&self, _point: &Vector, _rng: &mut dyn RngCore) -> Option<LightSample> {
+        Some(LightSample {
+            direction: -self.direction.normalize(),
+            distance: Float::INFINITY,
+            irradiance: self.irradiance,
+        })
+    }
+}
+
+/// Light emitted equally in all directions from a point, falling off with squared distance.
+pub struct PointLight {
+    pub position: Vector,
+    /// Radiant intensity, the irradiance at unit distance.
+    pub intensity: Vector,
+}
+
+impl Light for PointLight {
+    fn sample(&self, point: &Vector, _rng: &mut dyn RngCore) -> Option<LightSample> {
+        let offset = self.position - *point;
+        let distance = offset.length();
+        if distance < MIN_DISTANCE {
+            return None;
+        }
+        Some(LightSample {
+            direction: offset / distance,
+            distance,
+            irradiance: self.intensity / (distance * distance),
+        })
+    }
+}
+
+/// Point light restricted to a cone. The intensity is full inside the inner cone and fades
+/// smoothly to nothing at the outer cone; angles are measured from `direction`.
+pub struct SpotLight {
+    pub position: Vector,
+    /// Direction the cone points in.
+    pub direction: Vector,
+    pub intensity: Vector,
+    pub inner_cone_degrees: Float,
+    pub outer_cone_degrees: Float,
+}
+
+impl SpotLight {
+    fn falloff(&self, cos_angle: Float) -> Float {
+        let cos_outer = self.outer_cone_degrees.to_radians().cos();
+        let cos_inner = self.inner_cone_degrees.to_radians().cos().max(cos_outer);
+        if cos_angle >= cos_inner {
+            return 1.0;
+        }
+        let t = ((cos_angle - cos_outer) / (cos_inner - cos_outer)).clamp(0.0, 1.0);
+        t * t * (3.0 - 2.0 * t)
+    }
+}
+
+impl Light for SpotLight {
+    fn sample(&self, point: &Vector, _rng: &mut dyn RngCore) -> Option<LightSample> {
+        let offset = self.position - *point;
+        let distance = offset.length();
+        if distance < MIN_DISTANCE {
+            return None;
+        }
+        let direction = offset / distance;
+        let falloff = self.falloff(-direction.dot(&self.direction.normalize()));
+        if falloff <= 0.0 {
+            return None;
+        }
+        Some(LightSample {
+            direction,
+            distance,
+            irradiance: self.intensity * (falloff / (distance * distance)),
+        })
+    }
+}
+
+/// Relative distance within which a scene hit counts as a hit on an area light's shape.
+const HIT_TOLERANCE: Float = 1e-4;
+
+/// Emitting surface such as a `Sphere`, `Quad`, `Disk` or `TriangleMesh`, sampled by area.
+/// Only the side its normals point to emits. To make it visible, add the same `Arc` to the
+/// scene with an `Emissive` material of the same radiance.
+pub struct AreaLight {
+    pub shape: Arc<dyn Hittable>,
+    pub radiance: Vector,
+}
+
+impl AreaLight {
+    pub fn new(shape: Arc<dyn Hittable>, radiance: Vector) -> AreaLight {
+        AreaLight { shape, radiance }
+    }
+}
+
+impl Light for AreaLight {
+    fn sample(&self, point: &Vector, rng: &mut dyn RngCore) -> Option<LightSample> {
+        let sample = self.shape.sample_surface(rng.gen(), rng.gen());
+        let offset = sample.point - *point;
+        let distance_squared = offset.length_squared();
+        let distance = distance_squared.sqrt();
+        if distance < MIN_DISTANCE || sample.pdf <= 0.0 {
+            return None;
+        }
+        let direction = offset / distance;
+        let cos_light = -direction.dot(&sample.normal);
+        if cos_light <= 0.0 {
+            return None;
+        }
+        let pdf = sample.pdf * distance_squared / cos_light;
+        Some(LightSample {
+            direction,
+            distance,
+            irradiance: self.radiance / pdf,
+        })
+    }
+
+    fn is_hit(&self, ray: &Ray, t: Float) -> bool {
+        self.shape.occluded(ray, t * (1.0 - HIT_TOLERANCE), t * (1.0 + HIT_TOLERANCE))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::SmallRng;
+    use rand::SeedableRng;
+
+    use super::*;
+    use crate::material::MaterialId;
+    use crate::quad::Quad;
+
+    #[test]
+    fn points_on_a_light_receive_nothing() {
+        let mut rng = SmallRng::seed_from_u64(1);
+        let position = Vector::new(1.0, 2.0, 3.0);
+        let point = PointLight {
+            position,
+            intensity: Vector::splat(4.0),
+        };
+        let spot = SpotLight {
+            position,
+            direction: Vector::new(0.0, -1.0, 0.0),
+            intensity: Vector::splat(4.0),
+            inner_cone_degrees: 20.0,
+            outer_cone_degrees: 30.0,
+        };
+        assert!(point.sample(&position, &mut rng).is_none());
+        assert!(spot.sample(&position, &mut rng).is_none());
+
+        // Two units below, both give a quarter of their intensity.
+        let below = position - Vector::new(0.0, 2.0, 0.0);
+        for light in [&point as &dyn Light, &spot] {
+            let sample = light.sample(&below, &mut rng).unwrap();
+            assert_eq!((sample.direction, sample.distance, sample.irradiance), (Vector::new(0.0, 1.0, 0.0), 2.0, Vector::splat(1.0)));
+        }
+    }
+
+    #[test]
+    fn spot_lights_fade_between_their_cones() {
+        let mut rng = SmallRng::seed_from_u64(1);
+        let spot = SpotLight {
+            position: Vector::zero(),
+            direction: Vector::new(0.0, -2.0, 0.0),
+            intensity: Vector::splat(1.0),
+            inner_cone_degrees: 20.0,
+            outer_cone_degrees: 30.0,
+        };
+        // Unit distance away, `degrees` off the axis.
+        let mut irradiance = |degrees: Float| {
+            let angle = degrees.to_radians();
+            spot.sample(&Vector::new(angle.sin(), -angle.cos(), 0.0), &mut rng).map(|sample| sample.irradiance.x)
+        };
+        assert_eq!(irradiance(0.0), Some(1.0));
+        assert!((irradiance(19.0).unwrap() - 1.0).abs() < 1e-5);
+
+        let [cos_inner, cos_outer] = [20.0 as Float, 30.0].map(|degrees| degrees.to_radians().cos());
+        let t = ((25.0 as Float).to_radians().cos() - cos_outer) / (cos_inner - cos_outer);
+        let expected = t * t * (3.0 - 2.0 * t);
+        assert!((irradiance(25.0).unwrap() - expected).abs() < 1e-5);
+        assert!(irradiance(21.0).unwrap() > irradiance(25.0).unwrap() && irradiance(29.0).unwrap() > 0.0);
+
+        assert!(irradiance(31.0).is_none());
+        assert!(irradiance(180.0).is_none());
+    }
+
+    #[test]
+    fn directional_lights_arrive_from_infinitely_far_away() {
+        let mut rng = SmallRng::seed_from_u64(1);
+        let sun = DirectionalLight {
+            direction: Vector::new(0.0, -3.0, 4.0),
+            irradiance: Vector::new(1.0, 0.9, 0.8),
+        };
+        for point in [Vector::zero(), Vector::new(100.0, -50.0, 7.0)] {
+            let sample = sun.sample(&point, &mut rng).unwrap();
+            assert!((sample.direction - Vector::new(0.0, 0.6, -0.8)).length() < 1e-9);
+            assert_eq!((sample.distance, sample.irradiance), (Float::INFINITY, sun.irradiance));
+        }
+    }
+
+    /// A square of side `size` centered at (0, 2, 0), turned `degrees` about z from facing the
+    /// origin; `back` turns it away instead.
+    fn square(size: Float, degrees: Float, back: bool) -> Arc<dyn Hittable> {
+        let angle = degrees.to_radians();
+        let u = Vector::new(0.0, 0.0, size);
+        let v = Vector::new(-angle.cos(), -angle.sin(), 0.0) * size;
+        let (u, v) = if back { (v, u) } else { (u, v) };
+        Arc::new(Quad::new(Vector::new(0.0, 2.0, 0.0) - (u + v) / 2.0, u, v, MaterialId::default()).unwrap())
+    }
+
+    #[test]
+    fn area_lights_convert_to_solid_angle() {
+        let mut rng = SmallRng::seed_from_u64(1);
+        // Tilted by 60 degrees two units away, a small square subtends about A cos(60) / 4.
+        let light = AreaLight::new(square(0.01, 60.0, false), Vector::splat(1000.0));
+        let expected = 1000.0 * 0.01 * 0.01 * 0.5 / 4.0;
+        for _ in 0..100 {
+            let sample = light.sample(&Vector::zero(), &mut rng).unwrap();
+            assert!((sample.irradiance.x - expected).abs() < 0.02 * expected, "{:?} != {expected}", sample.irradiance);
+            assert!((sample.distance - 2.0).abs() < 0.01 && (sample.direction - Vector::new(0.0, 1.0, 0.0)).length() < 0.01);
+        }
+    }
+
+    #[test]
+    fn area_lights_send_nothing_from_their_back() {
+        let mut rng = SmallRng::seed_from_u64(1);
+        let light = AreaLight::new(square(0.5, 30.0, true), Vector::splat(1.0));
+        for _ in 0..100 {
+            assert!(light.sample(&Vector::zero(), &mut rng).is_none());
+        }
+    }
+
+    #[test]
+    fn area_lights_are_hit_only_on_their_shape() {
+        let shape = square(1.0, 0.0, false);
+        let light = AreaLight::new(shape.clone(), Vector::splat(1.0));
+        let ray = Ray {
+            origin: Vector::zero(),
+            direction: Vector::new(0.0, 0.5, 0.0),
+        };
+        let t = shape.intersect(&ray, 0.0, Float::INFINITY).unwrap().t;
+        assert_eq!(t, 4.0);
+        assert!(light.is_hit(&ray, t));
+        // Something else hit before or after the light, or along another ray, is not the light.
+        assert!(!light.is_hit(&ray, 3.0));
+        assert!(!light.is_hit(&ray, 5.0));
+        let aside = Ray {
+            origin: Vector::zero(),
+            direction: Vector::new(1.0, 0.0, 0.0),
+        };
+        assert!(!light.is_hit(&aside, 2.0));
+    }
+}
@@ -0,0 +1,833 @@
+//! Surface materials. Colors are linear reflectances in `[0, 1]`; emission is unbounded radiance.
+
+use rand::{Rng, RngCore};
+
+use crate::hittable::HitRecord;
+use crate::math::consts::PI;
+use crate::math::Float;
+use crate::ray::Ray;
+use crate::texture::ImageTexture;
+use crate::vector::Vector;
+
+/// Index of a material in its scene's palette.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct MaterialId(pub usize);
+
+/// A sampled continuation of a path.
+pub struct Scatter {
+    /// Unit direction the light arrives from, pointing away from the surface.
+    pub direction: Vector,
+    /// BSDF times cosine over `pdf`, or the reflectance for specular scattering.
+    pub weight: Vector,
+    /// Solid-angle density of `direction`; unused for specular scattering.
+    pub pdf: Float,
+    /// Whether `direction` came from a delta distribution, which `evaluate` cannot match.
+    pub specular: bool,
+}
+
+/// The perfectly specular part of a material, split between the mirror direction and a
+/// refracted one, for tracers that follow both instead of sampling one.
+pub struct Specular {
+    pub reflectance: Vector,
+    pub transmittance: Vector,
+    /// Unit refracted direction; `None` for opaque surfaces and under total internal reflection.
+    pub refracted: Option<Vector>,
+}
+
+impl Specular {
+    pub fn none() -> Specular {
+        Specular {
+            reflectance: Vector::zero(),
+            transmittance: Vector::zero(),
+            refracted: None,
+        }
+    }
+}
+
+/// Directions are unit vectors pointing away from the surface: `outgoing` toward the viewer
+/// (`-ray.direction`) and `incoming` toward the light.
+pub trait Material: Send + Sync {
+    /// Samples an incoming direction, or `None` if the path is absorbed.
+    fn scatter(&self, ray: &Ray, hit: &HitRecord, rng: &mut dyn RngCore) -> Option<Scatter>;
+
+    /// BSDF times the cosine of `incoming`, excluding any specular part.
+    fn evaluate(&self, _hit: &HitRecord, _outgoing: &Vector, _incoming: &Vector) -> Vector {
+        Vector::zero()
+    }
+
+    /// Density with which `scatter` picks `incoming`, excluding any specular part.
+    fn pdf(&self, _hit: &HitRecord, _outgoing: &Vector, _incoming: &Vector) -> Float {
+        0.0
+    }
+
+    /// Reflectance of the scene's ambient light, a cheap stand-in for indirect light.
+    fn ambient(&self, _hit: &HitRecord) -> Vector {
+        Vector::zero()
+    }
+
+    /// Weights of the mirror and refracted rays; rough and diffuse lobes are left out.
+    fn specular(&self, _ray: &Ray, _hit: &HitRecord) -> Specular {
+        Specular::none()
+    }
+
+    fn emitted(&self, _ray: &Ray, _hit: &HitRecord) -> Vector {
+        Vector::zero()
+    }
+}
+
+/// Cosine-weighted direction about the unit `normal`.
+pub fn sample_cosine_hemisphere(normal: &Vector, rng: &mut dyn RngCore) -> Vector {
+    let (u, v): (Float, Float) = (rng.gen(), rng.gen());
+    let radius = u.sqrt();
+    let phi = 2.0 * PI * v;
+    let (tangent, bitangent) = normal.orthonormal_basis();
+    (tangent * (radius * phi.cos()) + bitangent * (radius * phi.sin()) + *normal * (1.0 - u).max(0.0).sqrt()).normalize()
+}
+
+/// GGX microfacet normal about the unit `normal`, sampled in proportion to D(h) cos(theta_h).
+fn sample_ggx_half(normal: &Vector, alpha: Float, rng: &mut dyn RngCore) -> Vector {
+    let (u, v): (Float, Float) = (rng.gen(), rng.gen());
+    let tan2_theta = alpha * alpha * u / (1.0 - u).max(Float::EPSILON);
+    let cos_theta = 1.0 / (1.0 + tan2_theta).sqrt();
+    let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+    let phi = 2.0 * PI * v;
+    let (tangent, bitangent) = normal.orthonormal_basis();
+    tangent * (sin_theta * phi.cos()) + bitangent * (sin_theta * phi.sin()) + *normal * cos_theta
+}
+
+/// Unpolarized Fresnel reflectance of a dielectric boundary, with `eta` the transmitted over
+/// incident index of refraction. Returns 1 under total internal reflection.
+pub fn fresnel_dielectric(cos_i: Float, eta: Float) -> Float {
+    let cos_i = cos_i.clamp(0.0, 1.0);
+    let sin2_t = (1.0 - cos_i * cos_i) / (eta * eta);
+    if sin2_t >= 1.0 {
+        return 1.0;
+    }
+    let cos_t = (1.0 - sin2_t).sqrt();
+    let r_s = (cos_i - eta * cos_t) / (cos_i + eta * cos_t);
+    let r_p = (eta * cos_i - cos_t) / (eta * cos_i + cos_t);
+    (r_s * r_s + r_p * r_p) / 2.0
+}
+
+/// Schlick's approximation of Fresnel reflectance for normal-incidence reflectance `f0`.
+pub fn fresnel_schlick(cos_i: Float, f0: Vector) -> Vector {
+    let m = (1.0 - cos_i.clamp(0.0, 1.0)).powi(5);
+    f0 + (Vector::splat(1.0) - f0) * m
+}
+
+pub struct Lambertian {
+    pub albedo: Vector,
+}
+
+impl Lambertian {
+    /// Vertex colors take the place of the albedo.
+    fn albedo(&self, hit: &HitRecord) -> Vector {
+        hit.vertex_color.unwrap_or(self.albedo)
+    }
+}
+
+impl Material for Lambertian {
+    fn scatter(&self, _ray: &Ray, hit: &HitRecord, rng: &mut dyn RngCore) -> Option<Scatter> {
+        let direction = sample_cosine_hemisphere(&hit.normal, rng);
+        Some(Scatter {
+            direction,
+            weight: self.albedo(hit),
+            pdf: direction.dot(&hit.normal).max(0.0) / PI,
+            specular: false,
+        })
+    }
+
+    fn evaluate(&self, hit: &HitRecord, _outgoing: &Vector, incoming: &Vector) -> Vector {
+        self.albedo(hit) * (incoming.dot(&hit.normal).max(0.0) / PI)
+    }
+
+    fn pdf(&self, hit: &HitRecord, _outgoing: &Vector, incoming: &Vector) -> Float {
+        incoming.dot(&hit.normal).max(0.0) / PI
+    }
+}
+
+pub struct Mirror {
+    pub color: Vector,
+}
+
+impl Material for Mirror {
+    fn scatter(&self, ray: &Ray, hit: &HitRecord, _rng: &mut dyn RngCore) -> Option<Scatter> {
+        Some(Scatter {
+            direction: ray.direction.normalize().reflect(&hit.normal),
+            weight: self.color,
+            pdf: 0.0,
+            specular: true,
+        })
+    }
+
+    fn specular(&self, _ray: &Ray, _hit: &HitRecord) -> Specular {
+        Specular {
+            reflectance: self.color,
+            ..Specular::none()
+        }
+    }
+}
+
+/// Conductor with a GGX microfacet distribution; `color` is the reflectance at normal
+/// incidence and `roughness` runs from polished (0) to matte (1).
+pub struct Metal {
+    pub color: Vector,
+    pub roughness: Float,
+}
+
+impl Metal {
+    /// Below this GGX alpha the lobe is treated as a perfect mirror.
+    const MIN_ALPHA: Float = 1e-3;
+
+    fn alpha(&self) -> Float {
+        self.roughness.clamp(0.0, 1.0).powi(2)
+    }
+
+    fn distribution(alpha: Float, cos_h: Float) -> Float {
+        let a2 = alpha * alpha;
+        let d = cos_h * cos_h * (a2 - 1.0) + 1.0;
+        a2 / (PI * d * d)
+    }
+
+    fn masking(alpha: Float, cos_v: Float) -> Float {
+        let a2 = alpha * alpha;
+        2.0 * cos_v / (cos_v + (a2 + (1.0 - a2) * cos_v * cos_v).sqrt())
+    }
+}
+
+impl Material for Metal {
+    fn scatter(&self, ray: &Ray, hit: &HitRecord, rng: &mut dyn RngCore) -> Option<Scatter> {
+        let outgoing = -ray.direction.normalize();
+        let alpha = self.alpha();
+        if alpha < Metal::MIN_ALPHA {
+            return Some(Scatter {
+                direction: ray.direction.normalize().reflect(&hit.normal),
+                weight: fresnel_schlick(outgoing.dot(&hit.normal), self.color),
+                pdf: 0.0,
+                specular: true,
+            });
+        }
+
+        let half = sample_ggx_half(&hit.normal, alpha, rng);
+        let incoming = (-outgoing).reflect(&half);
+        if incoming.dot(&hit.normal) <= 0.0 {
+            return None;
+        }
+        let pdf = self.pdf(hit, &outgoing, &incoming);
+        if pdf <= 0.0 {
+            return None;
+        }
+        Some(Scatter {
+            direction: incoming,
+            weight: self.evaluate(hit, &outgoing, &incoming) / pdf,
+            pdf,
+            specular: false,
+        })
+    }
+
+    fn evaluate(&self, hit: &HitRecord, outgoing: &Vector, incoming: &Vector) -> Vector {
+        let alpha = self.alpha();
+        let (cos_o, cos_i) = (outgoing.dot(&hit.normal), incoming.dot(&hit.normal));
+        if alpha < Metal::MIN_ALPHA || cos_o <= 0.0 || cos_i <= 0.0 {
+            return Vector::zero();
+        }
+        let half = (*outgoing + *incoming).normalize();
+        let d = Metal::distribution(alpha, half.dot(&hit.normal));
+        let g = Metal::masking(alpha, cos_o) * Metal::masking(alpha, cos_i);
+        fresnel_schlick(incoming.dot(&half), self.color) * (d * g / (4.0 * cos_o))
+    }
+
+    fn pdf(&self, hit: &HitRecord, outgoing: &Vector, incoming: &Vector) -> Float {
+        let alpha = self.alpha();
+        if alpha < Metal::MIN_ALPHA || incoming.dot(&hit.normal) <= 0.0 {
+            return 0.0;
+        }
+        let half = (*outgoing + *incoming).normalize();
+        let cos_h = half.dot(&hit.normal).max(0.0);
+        Metal::distribution(alpha, cos_h) * cos_h / (4.0 * outgoing.dot(&half).abs().max(Float::EPSILON))
+    }
+
+    fn specular(&self, ray: &Ray, hit: &HitRecord) -> Specular {
+        if self.alpha() >= Metal::MIN_ALPHA {
+            return Specular::none();
+        }
+        Specular {
+            reflectance: fresnel_schlick(-ray.direction.normalize().dot(&hit.normal), self.color),
+            ..Specular::none()
+        }
+    }
+}
+
+/// Blinn-Phong shading: a diffuse lobe in `color` plus a highlight in `specular_color` that
+/// narrows as `shininess` grows. The coefficients scale the ambient, diffuse and specular
+/// terms; the highlight is normalized so raising `shininess` does not brighten it overall.
+pub struct Phong {
+    pub color: Vector,
+    pub specular_color: Vector,
+    pub ambient: Float,
+    pub diffuse: Float,
+    pub specular: Float,
+    pub shininess: Float,
+}
+
+impl Phong {
+    fn color(&self, hit: &HitRecord) -> Vector {
+        hit.vertex_color.unwrap_or(self.color)
+    }
+
+    /// Chance that `scatter` samples the highlight rather than the diffuse lobe.
+    fn specular_probability(&self, hit: &HitRecord) -> Float {
+        let diffuse = self.diffuse * self.color(hit).max_component();
+        let specular = self.specular * self.specular_color.max_component();
+        if diffuse + specular > 0.0 { specular / (diffuse + specular) } else { 0.0 }
+    }
+}
+
+impl Material for Phong {
+    fn scatter(&self, ray: &Ray, hit: &HitRecord, rng: &mut dyn RngCore) -> Option<Scatter> {
+        let outgoing = -ray.direction.normalize();
+        let incoming = if rng.gen::<Float>() < self.specular_probability(hit) {
+            // Sample the half vector about the normal in proportion to cos^shininess.
+            let (u, v): (Float, Float) = (rng.gen(), rng.gen());
+            let cos_theta = u.powf(1.0 / (self.shininess + 1.0));
+            let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+            let phi = 2.0 * PI * v;
+            let (tangent, bitangent) = hit.normal.orthonormal_basis();
+            let half = tangent * (sin_theta * phi.cos()) + bitangent * (sin_theta * phi.sin()) + hit.normal * cos_theta;
+            (-outgoing).reflect(&half)
+        } else {
+            sample_cosine_hemisphere(&hit.normal, rng)
+        };
+        let pdf = self.pdf(hit, &outgoing, &incoming);
+        if incoming.dot(&hit.normal) <= 0.0 || pdf <= 0.0 {
+            return None;
+        }
+        Some(Scatter {
+            direction: incoming,
+            weight: self.evaluate(hit, &outgoing, &incoming) / pdf,
+            pdf,
+            specular: false,
+        })
+    }
+
+    fn evaluate(&self, hit: &HitRecord, outgoing: &Vector, incoming: &Vector) -> Vector {
+        let cos_i = incoming.dot(&hit.normal);
+        if cos_i <= 0.0 {
+            return Vector::zero();
+        }
+        let cos_h = (*outgoing + *incoming).normalize().dot(&hit.normal).max(0.0);
+        let highlight = (self.shininess + 8.0) / (8.0 * PI) * cos_h.powf(self.shininess);
+        (self.color(hit) * (self.diffuse / PI) + self.specular_color * (self.specular * highlight)) * cos_i
+    }
+
+    fn pdf(&self, hit: &HitRecord, outgoing: &Vector, incoming: &Vector) -> Float {
+        let cos_i = incoming.dot(&hit.normal);
+        if cos_i <= 0.0 {
+            return 0.0;
+        }
+        let half = (*outgoing + *incoming).normalize();
+        let cos_h = half.dot(&hit.normal).max(0.0);
+        let specular_pdf = (self.shininess + 1.0) / (2.0 * PI) * cos_h.powf(self.shininess) / (4.0 * outgoing.dot(&half).abs().max(Float::EPSILON));
+        let probability = self.specular_probability(hit);
+        (1.0 - probability) * cos_i / PI + probability * specular_pdf
+    }
+
+    fn ambient(&self, hit: &HitRecord) -> Vector {
+        self.color(hit) * self.ambient
+    }
+}
+
+/// Smooth glass or water. `tint` scales transmitted light; white is clear.
+pub struct Dielectric {
+    pub ior: Float,
+    pub tint: Vector,
+}
+
+impl Dielectric {
+    /// Index ratio transmitted over incident for a ray hitting this side of the surface.
+    pub fn eta(&self, hit: &HitRecord) -> Float {
+        if hit.front_face { self.ior } else { 1.0 / self.ior }
+    }
+}
+
+impl Material for Dielectric {
+    fn scatter(&self, ray: &Ray, hit: &HitRecord, rng: &mut dyn RngCore) -> Option<Scatter> {
+        let direction = ray.direction.normalize();
+        let eta = self.eta(hit);
+        let reflectance = fresnel_dielectric(-direction.dot(&hit.normal), eta);
+        let refracted = direction.refract(&hit.normal, 1.0 / eta);
+        let (direction, weight) = match refracted {
+            Some(refracted) if rng.gen::<Float>() >= reflectance => (refracted, self.tint),
+            _ => (direction.reflect(&hit.normal), Vector::splat(1.0)),
+        };
+        Some(Scatter {
+            direction,
+            weight,
+            pdf: 0.0,
+            specular: true,
+        })
+    }
+
+    fn specular(&self, ray: &Ray, hit: &HitRecord) -> Specular {
+        let direction = ray.direction.normalize();
+        let eta = self.eta(hit);
+        match direction.refract(&hit.normal, 1.0 / eta) {
+            Some(refracted) => {
+                let reflectance = fresnel_dielectric(-direction.dot(&hit.normal), eta);
+                Specular {
+                    reflectance: Vector::splat(reflectance),
+                    transmittance: self.tint * (1.0 - reflectance),
+                    refracted: Some(refracted),
+                }
+            }
+            None => Specular {
+                reflectance: Vector::splat(1.0),
+                ..Specular::none()
+            },
+        }
+    }
+}
+
+/// glTF's metallic-roughness model: a GGX lobe over a Lambertian base, blended by `metallic`.
+/// Dielectrics reflect 4% at normal incidence and metals reflect their base color. The blue and
+/// green channels of a `metallic_roughness` texture scale the two factors. `emissive`, times
+/// the sRGB `emissive_texture`, is emitted from the front face, or from both when
+/// `double_sided`.
+pub struct MetallicRoughness {
+    pub base_color: Vector,
+    pub metallic: Float,
+    pub roughness: Float,
+    pub metallic_roughness: Option<ImageTexture>,
+    pub emissive: Vector,
+    pub emissive_texture: Option<ImageTexture>,
+    pub double_sided: bool,
+}
+
+impl MetallicRoughness {
+    /// Normal-incidence reflectance of the dielectric base.
+    const DIELECTRIC_F0: Float = 0.04;
+
+    /// Base color, metalness and GGX alpha at the hit. Vertex colors take the place of the
+    /// base color, and the alpha is kept above `Metal::MIN_ALPHA` so the lobe stays a density.
+    fn parameters(&self, hit: &HitRecord) -> (Vector, Float, Float) {
+        let (metallic, roughness) = match &self.metallic_roughness {
+            Some(texture) => {
+                let texel = texture.sample(hit.u, hit.v);
+                (self.metallic * texel.z, self.roughness * texel.y)
+            }
+            None => (self.metallic, self.roughness),
+        };
+        let alpha = roughness.clamp(0.0, 1.0).powi(2).max(Metal::MIN_ALPHA);
+        (hit.vertex_color.unwrap_or(self.base_color), metallic.clamp(0.0, 1.0), alpha)
+    }
+
+    /// Chance that `scatter` samples the GGX lobe rather than the diffuse one.
+    fn specular_probability(metallic: Float) -> Float {
+        (1.0 + metallic) / 2.0
+    }
+}
+
+impl Material for MetallicRoughness {
+    fn scatter(&self, ray: &Ray, hit: &HitRecord, rng: &mut dyn RngCore) -> Option<Scatter> {
+        let outgoing = -ray.direction.normalize();
+        let (_, metallic, alpha) = self.parameters(hit);
+        let incoming = if rng.gen::<Float>() < MetallicRoughness::specular_probability(metallic) {
+            (-outgoing).reflect(&sample_ggx_half(&hit.normal, alpha, rng))
+        } else {
+            sample_cosine_hemisphere(&hit.normal, rng)
+        };
+        let pdf = self.pdf(hit, &outgoing, &incoming);
+        if incoming.dot(&hit.normal) <= 0.0 || pdf <= 0.0 {
+            return None;
+        }
+        Some(Scatter {
+            direction: incoming,
+            weight: self.evaluate(hit, &outgoing, &incoming) / pdf,
+            pdf,
+            specular: false,
+        })
+    }
+
+    fn evaluate(&self, hit: &HitRecord, outgoing: &Vector, incoming: &Vector) -> Vector {
+        let (cos_o, cos_i) = (outgoing.dot(&hit.normal), incoming.dot(&hit.normal));
+        if cos_o <= 0.0 || cos_i <= 0.0 {
+            return Vector::zero();
+        }
+        let (base_color, metallic, alpha) = self.parameters(hit);
+        let half = (*outgoing + *incoming).normalize();
+        let f0 = Vector::splat(MetallicRoughness::DIELECTRIC_F0).lerp(&base_color, metallic);
+        let fresnel = fresnel_schlick(incoming.dot(&half), f0);
+        let d = Metal::distribution(alpha, half.dot(&hit.normal));
+        let g = Metal::masking(alpha, cos_o) * Metal::masking(alpha, cos_i);
+        // Light the specular lobe reflects at this viewing angle never reaches the diffuse base.
+        let diffuse = base_color * (Vector::splat(1.0) - fresnel_schlick(cos_o, f0)) * ((1.0 - metallic) * cos_i / PI);
+        diffuse + fresnel * (d * g / (4.0 * cos_o))
+    }
+
+    fn pdf(&self, hit: &HitRecord, outgoing: &Vector, incoming: &Vector) -> Float {
+        let cos_i = incoming.dot(&hit.normal);
+        if cos_i <= 0.0 {
+            return 0.0;
+        }
+        let (_, metallic, alpha) = self.parameters(hit);
+        let half = (*outgoing + *incoming).normalize();
+        let cos_h = half.dot(&hit.normal).max(0.0);
+        let specular_pdf = Metal::distribution(alpha, cos_h) * cos_h / (4.0 * outgoing.dot(&half).abs().max(Float::EPSILON));
+        let probability = MetallicRoughness::specular_probability(metallic);
+        (1.0 - probability) * cos_i / PI + probability * specular_pdf
+    }
+
+    fn emitted(&self, _ray: &Ray, hit: &HitRecord) -> Vector {
+        if !hit.front_face && !self.double_sided {
+            return Vector::zero();
+        }
+        match &self.emissive_texture {
+            Some(texture) => self.emissive * texture.sample(hit.u, hit.v),
+            None => self.emissive,
+        }
+    }
+}
+
+/// Image textures on top of another material. `color` times `tint` replaces the material's
+/// base color, the way vertex colors do. `normal` is a tangent-space normal map, with red along
+/// the tangent and green along the bitangent, whose tilt is scaled by `normal_scale`. `bump`
+/// then tilts the shading normal along its height gradient, scaled by `bump_scale`. Normal and
+/// bump mapping need a surface tangent and are skipped without one.
+pub struct Textured {
+    pub material: Box<dyn Material>,
+    pub color: Option<ImageTexture>,
+    pub tint: Vector,
+    pub normal: Option<ImageTexture>,
+    pub normal_scale: Float,
+    pub bump: Option<ImageTexture>,
+    pub bump_scale: Float,
+}
+
+impl Textured {
+    /// The hit as the wrapped material should see it.
+    fn shade(&self, hit: &HitRecord) -> HitRecord {
+        let mut shaded = *hit;
+        if let Some(color) = &self.color {
+            shaded.vertex_color = Some(color.sample(hit.u, hit.v) * hit.vertex_color.unwrap_or(self.tint));
+        }
+        if let (Some(normal_map), Some((tangent, bitangent))) = (&self.normal, hit.tangent) {
+            let texel = normal_map.sample(hit.u, hit.v) * 2.0 - Vector::splat(1.0);
+            let normal = ((tangent * texel.x + bitangent * texel.y) * self.normal_scale + hit.normal * texel.z).normalize();
+            if !normal.is_near_zero() {
+                shaded.normal = normal;
+            }
+        }
+        if let (Some(bump), Some((tangent, bitangent))) = (&self.bump, hit.tangent) {
+            // Central differences in height per texel, so the scale does not depend on resolution.
+            let (du, dv) = (1.0 / bump.width() as Float, 1.0 / bump.height() as Float);
+            let dh_du = (bump.height_at(hit.u + du, hit.v) - bump.height_at(hit.u - du, hit.v)) / 2.0;
+            let dh_dv = (bump.height_at(hit.u, hit.v + dv) - bump.height_at(hit.u, hit.v - dv)) / 2.0;
+            let normal = (shaded.normal - (tangent * dh_du + bitangent * dh_dv) * self.bump_scale).normalize();
+            if !normal.is_near_zero() {
+                shaded.normal = normal;
+            }
+        }
+        shaded
+    }
+}
+
+impl Material for Textured {
+    fn scatter(&self, ray: &Ray, hit: &HitRecord, rng: &mut dyn RngCore) -> Option<Scatter> {
+        self.material.scatter(ray, &self.shade(hit), rng)
+    }
+
+    fn evaluate(&self, hit: &HitRecord, outgoing: &Vector, incoming: &Vector) -> Vector {
+        self.material.evaluate(&self.shade(hit), outgoing, incoming)
+    }
+
+    fn pdf(&self, hit: &HitRecord, outgoing: &Vector, incoming: &Vector) -> Float {
+        self.material.pdf(&self.shade(hit), outgoing, incoming)
+    }
+
+    fn ambient(&self, hit: &HitRecord) -> Vector {
+        self.material.ambient(&self.shade(hit))
+    }
+
+    fn specular(&self, ray: &Ray, hit: &HitRecord) -> Specular {
+        self.material.specular(ray, &self.shade(hit))
+    }
+
+    fn emitted(&self, ray: &Ray, hit: &HitRecord) -> Vector {
+        self.material.emitted(ray, &self.shade(hit))
+    }
+}
+
+/// Partially transparent surface, like MTL's dissolve: a fraction `opacity` of the light meets
+/// `material` and the rest passes straight through. The wrapped material should not refract,
+/// since the pass-through takes the transmitted direction. Shadow rays still stop at the
+/// surface.
+pub struct Dissolve {
+    pub material: Box<dyn Material>,
+    pub opacity: Float,
+}
+
+impl Material for Dissolve {
+    fn scatter(&self, ray: &Ray, hit: &HitRecord, rng: &mut dyn RngCore) -> Option<Scatter> {
+        if rng.gen::<Float>() >= self.opacity {
+            return Some(Scatter {
+                direction: ray.direction.normalize(),
+                weight: Vector::splat(1.0),
+                pdf: 0.0,
+                specular: true,
+            });
+        }
+        let scatter = self.material.scatter(ray, hit, rng)?;
+        // The opacity scales the BSDF and the pdf alike, so the weight is unchanged.
+        Some(Scatter {
+            pdf: scatter.pdf * self.opacity,
+            ..scatter
+        })
+    }
+
+    fn evaluate(&self, hit: &HitRecord, outgoing: &Vector, incoming: &Vector) -> Vector {
+        self.material.evaluate(hit, outgoing, incoming) * self.opacity
+    }
+
+    fn pdf(&self, hit: &HitRecord, outgoing: &Vector, incoming: &Vector) -> Float {
+        self.material.pdf(hit, outgoing, incoming) * self.opacity
+    }
+
+    fn ambient(&self, hit: &HitRecord) -> Vector {
+        self.material.ambient(hit) * self.opacity
+    }
+
+    fn specular(&self, ray: &Ray, hit: &HitRecord) -> Specular {
+        Specular {
+            reflectance: self.material.specular(ray, hit).reflectance * self.opacity,
+            transmittance: Vector::splat(1.0 - self.opacity),
+            refracted: Some(ray.direction.normalize()),
+        }
+    }
+
+    fn emitted(&self, ray: &Ray, hit: &HitRecord) -> Vector {
+        self.material.emitted(ray, hit) * self.opacity
+    }
+}
+
+/// Light-emitting surface; emits from its front face only and reflects nothing.
+pub struct Emissive {
+    pub radiance: Vector,
+}
+
+impl Material for Emissive {
+    fn scatter(&self, _ray: &Ray, _hit: &HitRecord, _rng: &mut dyn RngCore) -> Option<Scatter> {
+        None
+    }
+
+    fn emitted(&self, _ray: &Ray, hit: &HitRecord) -> Vector {
+        if hit.front_face { self.radiance } else { Vector::zero() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::SmallRng;
+    use rand::SeedableRng;
+
+    use super::*;
+
+    const SAMPLES: usize = 200_000;
+
+    fn hit() -> HitRecord {
+        HitRecord {
+            t: 1.0,
+            point: Vector::zero(),
+            normal: Vector::new(0.0, 0.0, 1.0),
+            front_face: true,
+            u: 0.0,
+            v: 0.0,
+            material: MaterialId::default(),
+            vertex_color: None,
+            tangent: None,
+        }
+    }
+
+    /// Ray arriving from `outgoing`, which points away from the surface.
+    fn ray(outgoing: Vector) -> Ray {
+        Ray {
+            origin: outgoing,
+            direction: -outgoing,
+        }
+    }
+
+    fn outgoing(degrees: Float) -> Vector {
+        let angle = degrees.to_radians();
+        Vector::new(angle.sin(), 0.0, angle.cos())
+    }
+
+    /// Directional albedo estimated two ways: averaging `scatter` weights, and integrating
+    /// `evaluate` over uniformly sampled hemisphere directions. They agree only if `scatter`,
+    /// `pdf` and `evaluate` describe the same lobe. Also checks every sampled pdf against `pdf`,
+    /// and returns the integral of `pdf` over the hemisphere last.
+    fn albedos(material: &dyn Material, outgoing: Vector) -> (Vector, Vector, Float) {
+        let mut rng = SmallRng::seed_from_u64(1);
+        let (hit, ray) = (hit(), ray(outgoing));
+        let mut sampled = Vector::zero();
+        for _ in 0..SAMPLES {
+            if let Some(scatter) = material.scatter(&ray, &hit, &mut rng) {
+                let pdf = material.pdf(&hit, &outgoing, &scatter.direction);
+                assert!((scatter.pdf - pdf).abs() <= 1e-4 * pdf.max(1.0), "{} != {pdf}", scatter.pdf);
+                let expected = material.evaluate(&hit, &outgoing, &scatter.direction) / pdf;
+                assert!((scatter.weight - expected).length() <= 1e-4 * expected.length().max(1.0));
+                sampled += scatter.weight;
+            }
+        }
+        let mut integrated = Vector::zero();
+        let mut pdf_integral = 0.0;
+        for _ in 0..SAMPLES {
+            let (u, v): (Float, Float) = (rng.gen(), rng.gen());
+            let radius = (1.0 - u * u).max(0.0).sqrt();
+            let phi = 2.0 * PI * v;
+            let incoming = Vector::new(radius * phi.cos(), radius * phi.sin(), u);
+            integrated += material.evaluate(&hit, &outgoing, &incoming) * (2.0 * PI);
+            pdf_integral += material.pdf(&hit, &outgoing, &incoming) * (2.0 * PI);
+        }
+        (sampled / SAMPLES as Float, integrated / SAMPLES as Float, pdf_integral / SAMPLES as Float)
+    }
+
+    fn assert_close(actual: Vector, expected: Vector, tolerance: Float) {
+        assert!((actual - expected).abs().max_component() <= tolerance, "{actual:?} != {expected:?}");
+    }
+
+    #[test]
+    fn white_lambertian_passes_the_furnace_test() {
+        let material = Lambertian { albedo: Vector::splat(1.0) };
+        for degrees in [0.0, 45.0, 80.0] {
+            let (sampled, integrated, pdf_integral) = albedos(&material, outgoing(degrees));
+            assert_close(sampled, Vector::splat(1.0), 1e-9);
+            assert_close(integrated, Vector::splat(1.0), 0.02);
+            assert!((pdf_integral - 1.0).abs() < 0.02, "pdf integrates to {pdf_integral}");
+        }
+    }
+
+    #[test]
+    fn ggx_metal_sampling_matches_its_lobe() {
+        for roughness in [0.4, 0.7, 1.0] {
+            let material = Metal {
+                color: Vector::splat(1.0),
+                roughness,
+            };
+            for degrees in [0.0, 45.0, 75.0] {
+                let (sampled, integrated, pdf_integral) = albedos(&material, outgoing(degrees));
+                assert_close(sampled, integrated, 0.03);
+                // Narrower lobes are too noisy to integrate over uniform directions.
+                if roughness == 1.0 {
+                    assert!(pdf_integral <= 1.01, "pdf integrates to {pdf_integral}");
+                }
+                // Masking and shadowing lose energy, but a white conductor never gains any.
+                assert!(sampled.max_component() <= 1.01, "{sampled:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn phong_sampling_matches_its_lobe() {
+        let material = Phong {
+            color: Vector::splat(1.0),
+            specular_color: Vector::splat(1.0),
+            ambient: 0.0,
+            diffuse: 0.5,
+            specular: 0.5,
+            shininess: 20.0,
+        };
+        for degrees in [0.0, 45.0] {
+            let (sampled, integrated, pdf_integral) = albedos(&material, outgoing(degrees));
+            assert_close(sampled, integrated, 0.03);
+            assert!(pdf_integral <= 1.01, "pdf integrates to {pdf_integral}");
+            // The (n + 8) / 8π normalization of the highlight is approximate, so it may gain a
+            // few percent.
+            assert!(sampled.max_component() <= 1.05, "{sampled:?}");
+        }
+    }
+
+    #[test]
+    fn metallic_roughness_sampling_matches_its_lobe() {
+        for metallic in [0.0, 0.5, 1.0] {
+            let material = MetallicRoughness {
+                base_color: Vector::splat(1.0),
+                metallic,
+                roughness: 0.5,
+                metallic_roughness: None,
+                emissive: Vector::zero(),
+                emissive_texture: None,
+                double_sided: false,
+            };
+            for degrees in [0.0, 30.0, 60.0, 80.0] {
+                let (sampled, integrated, _) = albedos(&material, outgoing(degrees));
+                assert_close(sampled, integrated, 0.03);
+                assert!(sampled.max_component() <= 1.01, "{sampled:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn dielectric_splits_energy_by_fresnel() {
+        let material = Dielectric {
+            ior: 1.5,
+            tint: Vector::splat(1.0),
+        };
+        let mut rng = SmallRng::seed_from_u64(2);
+        for degrees in [0.0, 45.0, 85.0] {
+            let outgoing = outgoing(degrees);
+            let (hit, ray) = (hit(), ray(outgoing));
+            let specular = material.specular(&ray, &hit);
+            assert_close(specular.reflectance + specular.transmittance, Vector::splat(1.0), 1e-9);
+            let fresnel = fresnel_dielectric(outgoing.z, 1.5);
+            assert!((specular.reflectance.x - fresnel).abs() < 1e-9);
+
+            // Sampling picks reflection with the Fresnel probability and weighs both paths fully.
+            let mut reflected = 0;
+            for _ in 0..SAMPLES {
+                let scatter = material.scatter(&ray, &hit, &mut rng).unwrap();
+                assert!(scatter.specular);
+                assert_close(scatter.weight, Vector::splat(1.0), 1e-9);
+                reflected += usize::from(scatter.direction.dot(&hit.normal) > 0.0);
+            }
+            assert!((reflected as Float / SAMPLES as Float - fresnel).abs() < 0.01);
+        }
+
+        // Inside the glass beyond the critical angle everything reflects.
+        let inside = HitRecord {
+            front_face: false,
+            ..hit()
+        };
+        let specular = material.specular(&ray(outgoing(60.0)), &inside);
+        assert_eq!(specular.reflectance, Vector::splat(1.0));
+        assert!(specular.refracted.is_none());
+    }
+
+    #[test]
+    fn dissolve_passes_the_transparent_fraction_straight_through() {
+        let material = Dissolve {
+            material: Box::new(Lambertian { albedo: Vector::splat(0.5) }),
+            opacity: 0.3,
+        };
+        let outgoing = outgoing(30.0);
+        let (hit, ray) = (hit(), ray(outgoing));
+        let mut rng = SmallRng::seed_from_u64(3);
+        let mut passed = 0;
+        for _ in 0..SAMPLES {
+            let scatter = material.scatter(&ray, &hit, &mut rng).unwrap();
+            if scatter.specular {
+                assert_close(scatter.direction, -outgoing, 1e-9);
+                assert_eq!(scatter.weight, Vector::splat(1.0));
+                passed += 1;
+            } else {
+                assert!((scatter.pdf - material.pdf(&hit, &outgoing, &scatter.direction)).abs() < 1e-9);
+                assert_close(scatter.weight, Vector::splat(0.5), 1e-6);
+            }
+        }
+        assert!((passed as Float / SAMPLES as Float - 0.7).abs() < 0.01);
+
+        let up = Vector::new(0.0, 0.0, 1.0);
+        assert_close(material.evaluate(&hit, &outgoing, &up), Vector::splat(0.3 * 0.5 / PI), 1e-9);
+        let specular = material.specular(&ray, &hit);
+        assert_close(specular.transmittance, Vector::splat(0.7), 1e-9);
+        assert_close(specular.refracted.unwrap(), -outgoing, 1e-9);
+    }
+}
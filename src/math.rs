@@ -0,0 +1,58 @@
+//! Scalar precision and the cgmath types built on it. Enable the `f32` feature to trade
+//! precision for memory and speed on large scenes.
+
+#[cfg(not(feature = "f32"))]
+pub type Float = f64;
+#[cfg(feature = "f32")]
+pub type Float = f32;
+
+#[cfg(not(feature = "f32"))]
+pub use std::f64::consts;
+#[cfg(feature = "f32")]
+pub use std::f32::consts;
+
+pub use cgmath::{Deg, Rad};
+
+use crate::vector::Vector;
+
+/// Widens to `f64` for numerically sensitive code, whichever precision `Float` has.
+#[allow(clippy::unnecessary_cast)]
+pub fn to_f64(value: Float) -> f64 {
+    value as f64
+}
+
+/// Narrows to `f32` for SIMD lanes, whichever precision `Float` has.
+#[allow(clippy::unnecessary_cast)]
+pub fn to_f32(value: Float) -> f32 {
+    value as f32
+}
+
+pub type Point3 = cgmath::Point3<Float>;
+pub type Vector3 = cgmath::Vector3<Float>;
+pub type Matrix3 = cgmath::Matrix3<Float>;
+pub type Matrix4 = cgmath::Matrix4<Float>;
+pub type Quaternion = cgmath::Quaternion<Float>;
+
+impl From<Vector3> for Vector {
+    fn from(v: Vector3) -> Vector {
+        Vector::new(v.x, v.y, v.z)
+    }
+}
+
+impl From<Vector> for Vector3 {
+    fn from(v: Vector) -> Vector3 {
+        Vector3::new(v.x, v.y, v.z)
+    }
+}
+
+impl From<Point3> for Vector {
+    fn from(p: Point3) -> Vector {
+        Vector::new(p.x, p.y, p.z)
+    }
+}
+
+impl From<Vector> for Point3 {
+    fn from(v: Vector) -> Point3 {
+        Point3::new(v.x, v.y, v.z)
+    }
+}
@@ -0,0 +1,581 @@
+use std::fmt;
+use std::sync::{Arc, OnceLock};
+
+use crate::aabb::Aabb;
+use crate::bvh::{Bvh, REBUILD_COST_RATIO};
+use crate::hittable::{HitRecord, Hittable, SurfaceSample};
+use crate::material::MaterialId;
+use crate::math::{to_f64, Float};
+use crate::ray::Ray;
+use crate::vector::Vector;
+use crate::wide_bvh::WideBvh;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MeshError {
+    /// A triangle refers to a vertex past the end of `positions`.
+    IndexOutOfRange { triangle: usize, index: u32, vertex_count: usize },
+    /// A per-vertex attribute does not have one entry per position.
+    AttributeLength { attribute: &'static str, count: usize, vertex_count: usize },
+}
+
+impl fmt::Display for MeshError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MeshError::IndexOutOfRange { triangle, index, vertex_count } => {
+                write!(f, "triangle {triangle}: vertex index {index} out of range (0..{vertex_count})")
+            }
+            MeshError::AttributeLength { attribute, count, vertex_count } => {
+                write!(f, "mesh has {vertex_count} vertices but {count} {attribute}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for MeshError {}
+
+/// Indexed triangle mesh with optional per-vertex shading attributes. Its BVH is built on the
+/// first intersection and traversed in its four-wide form; call `update_bvh` after moving
+/// vertices.
+///
+/// Intersection indexes the attributes directly, so meshes filled in by hand should pass
+/// `validate` first; the loaders do.
+pub struct TriangleMesh {
+    pub positions: Vec<Vector>,
+    pub indices: Vec<[u32; 3]>,
+    pub normals: Option<Vec<Vector>>,
+    pub uvs: Option<Vec<[Float; 2]>>,
+    pub tangents: Option<Vec<Vector>>,
+    /// Per-vertex handedness of the tangent frame, 1 or -1; the bitangent is
+    /// `sign * normal × tangent`. Mirrored UVs give -1, and `None` means 1 everywhere.
+    pub bitangent_signs: Option<Vec<Float>>,
+    /// Per-vertex linear colors, which materials use in place of their base color.
+    pub colors: Option<Vec<Vector>>,
+    pub material: MaterialId,
+    bvh: OnceLock<Bvh>,
+    wide_bvh: OnceLock<WideBvh>,
+    /// Running sum of the triangle areas, for area sampling.
+    area_cdf: OnceLock<Vec<Float>>,
+}
+
+impl TriangleMesh {
+    pub fn new(positions: Vec<Vector>, indices: Vec<[u32; 3]>, material: MaterialId) -> TriangleMesh {
+        TriangleMesh {
+            positions,
+            indices,
+            normals: None,
+            uvs: None,
+            tangents: None,
+            bitangent_signs: None,
+            colors: None,
+            material,
+            bvh: OnceLock::new(),
+            wide_bvh: OnceLock::new(),
+            area_cdf: OnceLock::new(),
+        }
+    }
+
+    pub fn with_normals(mut self, normals: Vec<Vector>) -> TriangleMesh {
+        self.normals = Some(normals);
+        self
+    }
+
+    pub fn with_uvs(mut self, uvs: Vec<[Float; 2]>) -> TriangleMesh {
+        self.uvs = Some(uvs);
+        self
+    }
+
+    pub fn with_colors(mut self, colors: Vec<Vector>) -> TriangleMesh {
+        self.colors = Some(colors);
+        self
+    }
+
+    /// Checks that every index names a vertex and every attribute has one entry per vertex.
+    pub fn validate(&self) -> Result<(), MeshError> {
+        let vertex_count = self.positions.len();
+        for (triangle, indices) in self.indices.iter().enumerate() {
+            if let Some(index) = indices.iter().find(|index| **index as usize >= vertex_count) {
+                return Err(MeshError::IndexOutOfRange {
+                    triangle,
+                    index: *index,
+                    vertex_count,
+                });
+            }
+        }
+        let lengths = [
+            ("normals", self.normals.as_ref().map(Vec::len)),
+            ("uvs", self.uvs.as_ref().map(Vec::len)),
+            ("tangents", self.tangents.as_ref().map(Vec::len)),
+            ("bitangent signs", self.bitangent_signs.as_ref().map(Vec::len)),
+            ("colors", self.colors.as_ref().map(Vec::len)),
+        ];
+        for (attribute, count) in lengths {
+            match count {
+                Some(count) if count != vertex_count => {
+                    return Err(MeshError::AttributeLength {
+                        attribute,
+                        count,
+                        vertex_count,
+                    })
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    pub fn triangle_count(&self) -> usize {
+        self.indices.len()
+    }
+
+    fn vertices(&self, triangle: usize) -> [Vector; 3] {
+        let [i0, i1, i2] = self.indices[triangle];
+        [self.positions[i0 as usize], self.positions[i1 as usize], self.positions[i2 as usize]]
+    }
+
+    pub fn triangle_area(&self, triangle: usize) -> Float {
+        let [p0, p1, p2] = self.vertices(triangle);
+        (p1 - p0).cross(&(p2 - p0)).length() / 2.0
+    }
+
+    pub fn triangle_bounds(&self, triangle: usize) -> Aabb {
+        let [p0, p1, p2] = self.vertices(triangle);
+        Aabb::new(p0.min(&p1).min(&p2), p0.max(&p1).max(&p2))
+    }
+
+    pub fn bvh(&self) -> &Bvh {
+        self.bvh.get_or_init(|| {
+            let bounds = (0..self.indices.len()).map(|triangle| self.triangle_bounds(triangle)).collect::<Vec<Aabb>>();
+            Bvh::build(&bounds)
+        })
+    }
+
+    pub fn wide_bvh(&self) -> &WideBvh {
+        self.wide_bvh.get_or_init(|| WideBvh::from_bvh(self.bvh()))
+    }
+
+    /// Refits the BVH after `positions` changed, rebuilding it if refitting degraded it too far.
+    /// The wide BVH is refitted alongside and only collapsed again after a rebuild. Returns
+    /// whether it rebuilt.
+    pub fn update_bvh(&mut self) -> bool {
+        let bounds = (0..self.indices.len()).map(|triangle| self.triangle_bounds(triangle)).collect::<Vec<Aabb>>();
+        self.area_cdf = OnceLock::new();
+        let Some(bvh) = self.bvh.get_mut() else {
+            self.wide_bvh = OnceLock::new();
+            return false;
+        };
+        let rebuilt = bvh.refit_or_rebuild(&bounds, REBUILD_COST_RATIO);
+        match self.wide_bvh.get_mut() {
+            Some(wide_bvh) if !rebuilt => wide_bvh.refit(bvh),
+            _ => self.wide_bvh = OnceLock::new(),
+        }
+        rebuilt
+    }
+
+    /// Smooth vertex normals as the area-weighted average of the adjacent face normals.
+    pub fn compute_normals(&mut self) {
+        self.normals = Some(self.smooth_normals());
+    }
+
+    fn smooth_normals(&self) -> Vec<Vector> {
+        let mut normals = vec![Vector::zero(); self.positions.len()];
+        for triangle in 0..self.indices.len() {
+            let [p0, p1, p2] = self.vertices(triangle);
+            let face_normal = (p1 - p0).cross(&(p2 - p0));
+            for index in self.indices[triangle] {
+                normals[index as usize] += face_normal;
+            }
+        }
+        normals.iter().map(Vector::normalize).collect()
+    }
+
+    /// Per-vertex tangents along increasing `u`, orthogonalized against the vertex normals,
+    /// and the bitangent signs that point the frame along increasing `v`. Meshes without
+    /// normals use smooth ones for this but stay flat-shaded. Does nothing for meshes without
+    /// UVs.
+    pub fn compute_tangents(&mut self) {
+        let Some(uvs) = &self.uvs else {
+            return;
+        };
+        let mut tangents = vec![Vector::zero(); self.positions.len()];
+        let mut bitangents = vec![Vector::zero(); self.positions.len()];
+        for (triangle, indices) in self.indices.iter().enumerate() {
+            let [p0, p1, p2] = self.vertices(triangle);
+            let [uv0, uv1, uv2] = indices.map(|index| uvs[index as usize]);
+            let (e1, e2) = (p1 - p0, p2 - p0);
+            let (du1, dv1, du2, dv2) = (uv1[0] - uv0[0], uv1[1] - uv0[1], uv2[0] - uv0[0], uv2[1] - uv0[1]);
+            let determinant = du1 * dv2 - du2 * dv1;
+            if determinant.abs() < 1e-12 {
+                continue;
+            }
+            let tangent = (e1 * dv2 - e2 * dv1) / determinant;
+            let bitangent = (e2 * du1 - e1 * du2) / determinant;
+            for index in indices {
+                tangents[*index as usize] += tangent;
+                bitangents[*index as usize] += bitangent;
+            }
+        }
+        let smooth_normals;
+        let normals = match &self.normals {
+            Some(normals) => normals,
+            None => {
+                smooth_normals = self.smooth_normals();
+                &smooth_normals
+            }
+        };
+        self.tangents = Some(
+            tangents
+                .iter()
+                .zip(normals)
+                .map(|(tangent, normal)| (*tangent - *normal * normal.dot(tangent)).normalize())
+                .collect(),
+        );
+        self.bitangent_signs = Some(
+            (0..self.positions.len())
+                .map(|vertex| if normals[vertex].cross(&tangents[vertex]).dot(&bitangents[vertex]) < 0.0 { -1.0 } else { 1.0 })
+                .collect(),
+        );
+    }
+
+    /// Splits the mesh into individually hittable triangles sharing its vertex buffers.
+    pub fn triangles(self: &Arc<TriangleMesh>) -> Vec<Triangle> {
+        (0..self.indices.len())
+            .map(|index| Triangle {
+                mesh: self.clone(),
+                index,
+            })
+            .collect()
+    }
+
+    /// Watertight ray-triangle intersection (Woop, Benthin and Wald 2013). Edge tests are done in
+    /// a ray-aligned frame in `f64`, so rays through a shared edge hit exactly one of its triangles.
+    pub fn intersect_triangle(&self, triangle: usize, ray: &Ray, t_min: Float, t_max: Float) -> Option<HitRecord> {
+        let [p0, p1, p2] = self.vertices(triangle);
+        let direction = [to_f64(ray.direction.x), to_f64(ray.direction.y), to_f64(ray.direction.z)];
+
+        let kz = if direction[0].abs() > direction[1].abs() {
+            if direction[0].abs() > direction[2].abs() { 0 } else { 2 }
+        } else if direction[1].abs() > direction[2].abs() {
+            1
+        } else {
+            2
+        };
+        let mut kx = (kz + 1) % 3;
+        let mut ky = (kx + 1) % 3;
+        if direction[kz] < 0.0 {
+            std::mem::swap(&mut kx, &mut ky);
+        }
+        let sx = direction[kx] / direction[kz];
+        let sy = direction[ky] / direction[kz];
+        let sz = 1.0 / direction[kz];
+
+        let relative = |p: Vector| {
+            let p = p - ray.origin;
+            [to_f64(p.x), to_f64(p.y), to_f64(p.z)]
+        };
+        let (a, b, c) = (relative(p0), relative(p1), relative(p2));
+        let (ax, ay) = (a[kx] - sx * a[kz], a[ky] - sy * a[kz]);
+        let (bx, by) = (b[kx] - sx * b[kz], b[ky] - sy * b[kz]);
+        let (cx, cy) = (c[kx] - sx * c[kz], c[ky] - sy * c[kz]);
+
+        let u = cx * by - cy * bx;
+        let v = ax * cy - ay * cx;
+        let w = bx * ay - by * ax;
+        if (u < 0.0 || v < 0.0 || w < 0.0) && (u > 0.0 || v > 0.0 || w > 0.0) {
+            return None;
+        }
+        let determinant = u + v + w;
+        if determinant == 0.0 {
+            return None;
+        }
+
+        let t_scaled = u * sz * a[kz] + v * sz * b[kz] + w * sz * c[kz];
+        let t = (t_scaled / determinant) as Float;
+        if t <= t_min || t >= t_max {
+            return None;
+        }
+        let barycentric = [(u / determinant) as Float, (v / determinant) as Float, (w / determinant) as Float];
+
+        let point = ray.origin + ray.direction * t;
+        let geometric_normal = (p1 - p0).cross(&(p2 - p0)).normalize();
+        let (_, front_face) = HitRecord::face_normal(ray, geometric_normal);
+        let indices = self.indices[triangle].map(|index| index as usize);
+        let interpolate = |values: &[Vector]| {
+            values[indices[0]] * barycentric[0] + values[indices[1]] * barycentric[1] + values[indices[2]] * barycentric[2]
+        };
+
+        let shading_normal = match &self.normals {
+            Some(normals) => {
+                let normal = interpolate(normals).normalize();
+                if normal.dot(&geometric_normal) < 0.0 { -normal } else { normal }
+            }
+            None => geometric_normal,
+        };
+        let (u, v) = match &self.uvs {
+            Some(uvs) => {
+                let [uv0, uv1, uv2] = indices.map(|index| uvs[index]);
+                (
+                    uv0[0] * barycentric[0] + uv1[0] * barycentric[1] + uv2[0] * barycentric[2],
+                    uv0[1] * barycentric[0] + uv1[1] * barycentric[1] + uv2[1] * barycentric[2],
+                )
+            }
+            None => (barycentric[1], barycentric[2]),
+        };
+
+        // The interpolated tangent is made perpendicular to the shading normal again, and the
+        // whole frame flips on back faces so bump gradients keep their direction.
+        let tangent = self.tangents.as_deref().map(|tangents| {
+            let tangent = interpolate(tangents);
+            let tangent = (tangent - shading_normal * shading_normal.dot(&tangent)).normalize();
+            let sign = match &self.bitangent_signs {
+                Some(signs) if indices.iter().map(|index| signs[*index]).sum::<Float>() < 0.0 => -1.0,
+                _ => 1.0,
+            };
+            let bitangent = shading_normal.cross(&tangent) * sign;
+            if front_face { (tangent, bitangent) } else { (-tangent, -bitangent) }
+        });
+
+        Some(HitRecord {
+            t,
+            point,
+            normal: if front_face { shading_normal } else { -shading_normal },
+            front_face,
+            u,
+            v,
+            material: self.material,
+            vertex_color: self.colors.as_deref().map(interpolate),
+            tangent,
+        })
+    }
+
+    pub fn sample_triangle(&self, triangle: usize, u: Float, v: Float) -> SurfaceSample {
+        let [p0, p1, p2] = self.vertices(triangle);
+        let su = u.sqrt();
+        let (b1, b2) = (su * (1.0 - v), su * v);
+        SurfaceSample {
+            point: p0 + (p1 - p0) * b1 + (p2 - p0) * b2,
+            normal: (p1 - p0).cross(&(p2 - p0)).normalize(),
+            pdf: 1.0 / self.triangle_area(triangle),
+        }
+    }
+}
+
+impl Hittable for TriangleMesh {
+    fn intersect(&self, ray: &Ray, t_min: Float, t_max: Float) -> Option<HitRecord> {
+        self.wide_bvh().intersect(ray, t_min, t_max, |triangle, t_max| self.intersect_triangle(triangle, ray, t_min, t_max))
+    }
+
+    fn occluded(&self, ray: &Ray, t_min: Float, t_max: Float) -> bool {
+        self.wide_bvh().occluded(ray, t_min, t_max, |triangle| self.intersect_triangle(triangle, ray, t_min, t_max).is_some())
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bvh().bounding_box()
+    }
+
+    /// Picks a triangle in proportion to its area, reusing `u` within it. The area table is
+    /// built on the first call and searched in `O(log n)` after that. A mesh without area
+    /// returns a zero pdf.
+    fn sample_surface(&self, u: Float, v: Float) -> SurfaceSample {
+        let cdf = self.area_cdf.get_or_init(|| {
+            let mut total = 0.0;
+            (0..self.indices.len())
+                .map(|triangle| {
+                    total += self.triangle_area(triangle);
+                    total
+                })
+                .collect()
+        });
+        let total = cdf.last().copied().unwrap_or(0.0);
+        if total <= 0.0 {
+            return SurfaceSample {
+                point: self.positions.first().copied().unwrap_or_default(),
+                normal: Vector::new(0.0, 0.0, 1.0),
+                pdf: 0.0,
+            };
+        }
+        let target = u * total;
+        let triangle = cdf.partition_point(|sum| *sum <= target).min(cdf.len().saturating_sub(1));
+        let start = if triangle > 0 { cdf[triangle - 1] } else { 0.0 };
+        let area = cdf.get(triangle).map_or(0.0, |end| end - start);
+        let u = if area > 0.0 { ((target - start) / area).clamp(0.0, 1.0) } else { 0.0 };
+        let sample = self.sample_triangle(triangle, u, v);
+        SurfaceSample {
+            pdf: 1.0 / total,
+            ..sample
+        }
+    }
+}
+
+/// A single triangle of a shared mesh.
+pub struct Triangle {
+    mesh: Arc<TriangleMesh>,
+    index: usize,
+}
+
+impl Hittable for Triangle {
+    fn intersect(&self, ray: &Ray, t_min: Float, t_max: Float) -> Option<HitRecord> {
+        self.mesh.intersect_triangle(self.index, ray, t_min, t_max)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.mesh.triangle_bounds(self.index).padded(1e-6)
+    }
+
+    fn sample_surface(&self, u: Float, v: Float) -> SurfaceSample {
+        self.mesh.sample_triangle(self.index, u, v)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::SmallRng;
+    use rand::{Rng, SeedableRng};
+
+    use super::*;
+    use crate::math::consts::PI;
+
+    fn assert_close(actual: Vector, expected: Vector) {
+        assert!((actual - expected).length() < 1e-5, "{actual:?} != {expected:?}");
+    }
+
+    /// Six non-planar triangles around a shared center vertex, so every inner edge and the
+    /// center are shared.
+    fn fan() -> TriangleMesh {
+        let mut positions = vec![Vector::new(0.1, -0.05, 0.02)];
+        for k in 0..6 {
+            let angle = k as Float * PI / 3.0 + 0.1 * (k % 2) as Float;
+            let radius = 1.0 + 0.2 * (k % 3) as Float;
+            positions.push(Vector::new(radius * angle.cos(), radius * angle.sin(), 0.05 * k as Float));
+        }
+        let indices = (0..6).map(|k| [0, k + 1, (k + 1) % 6 + 1]).collect();
+        TriangleMesh::new(positions, indices, MaterialId::default())
+    }
+
+    /// Rays from random origins above and below, aimed at `target`.
+    fn hits_through(mesh: &TriangleMesh, target: Vector, rng: &mut SmallRng) -> bool {
+        let offset = Vector::new(rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0), rng.gen_range(0.5..2.0));
+        let origin = if rng.gen() { target + offset } else { target - offset };
+        let ray = Ray {
+            origin,
+            direction: target - origin,
+        };
+        (0..mesh.triangle_count()).any(|triangle| mesh.intersect_triangle(triangle, &ray, 0.0, Float::INFINITY).is_some())
+    }
+
+    #[test]
+    fn has_no_gaps_along_shared_edges_and_vertices() {
+        let mesh = fan();
+        let mut rng = SmallRng::seed_from_u64(7);
+        let center = mesh.positions[0];
+        let mut misses = 0;
+        for _ in 0..200_000 {
+            let rim = mesh.positions[rng.gen_range(1..7)];
+            let target = center.lerp(&rim, rng.gen_range(0.0..1.0));
+            misses += usize::from(!hits_through(&mesh, target, &mut rng));
+        }
+        for _ in 0..10_000 {
+            misses += usize::from(!hits_through(&mesh, center, &mut rng));
+        }
+        assert_eq!(misses, 0);
+    }
+
+    #[test]
+    fn interpolates_shading_attributes() {
+        let normals = vec![Vector::new(0.0, 0.0, 1.0), Vector::new(1.0, 0.0, 1.0).normalize(), Vector::new(0.0, 1.0, 1.0).normalize()];
+        let mesh = TriangleMesh::new(
+            vec![Vector::zero(), Vector::new(1.0, 0.0, 0.0), Vector::new(0.0, 1.0, 0.0)],
+            vec![[0, 1, 2]],
+            MaterialId::default(),
+        )
+        .with_normals(normals.clone())
+        .with_uvs(vec![[0.0, 0.0], [2.0, 0.0], [0.0, 4.0]]);
+        // (0.25, 0.25) has barycentric weights 0.5, 0.25 and 0.25.
+        let expected = (normals[0] * 0.5 + normals[1] * 0.25 + normals[2] * 0.25).normalize();
+        let ray = Ray {
+            origin: Vector::new(0.25, 0.25, 1.0),
+            direction: Vector::new(0.0, 0.0, -1.0),
+        };
+        let hit = mesh.intersect(&ray, 0.0, Float::INFINITY).unwrap();
+        assert!(hit.front_face);
+        assert!((hit.t - 1.0).abs() < 1e-6);
+        assert_close(hit.normal, expected);
+        assert!((hit.u - 0.5).abs() < 1e-6 && (hit.v - 1.0).abs() < 1e-6);
+
+        // From behind, the shading normal flips to face the ray.
+        let ray = Ray {
+            origin: Vector::new(0.25, 0.25, -1.0),
+            direction: Vector::new(0.0, 0.0, 1.0),
+        };
+        let hit = mesh.intersect(&ray, 0.0, Float::INFINITY).unwrap();
+        assert!(!hit.front_face);
+        assert_close(hit.normal, -expected);
+    }
+
+    #[test]
+    fn tangent_frames_follow_mirrored_uvs_and_flip_on_back_faces() {
+        // `u` runs towards -x, so the bitangent along +v needs a negative sign.
+        let mut mesh = TriangleMesh::new(
+            vec![Vector::zero(), Vector::new(1.0, 0.0, 0.0), Vector::new(0.0, 1.0, 0.0)],
+            vec![[0, 1, 2]],
+            MaterialId::default(),
+        )
+        .with_uvs(vec![[1.0, 0.0], [0.0, 0.0], [1.0, 1.0]]);
+        mesh.compute_tangents();
+        assert_eq!(mesh.bitangent_signs.as_deref(), Some(&[-1.0, -1.0, -1.0][..]));
+        assert!(mesh.normals.is_none());
+
+        let ray = Ray {
+            origin: Vector::new(0.25, 0.25, 1.0),
+            direction: Vector::new(0.0, 0.0, -1.0),
+        };
+        let (tangent, bitangent) = mesh.intersect(&ray, 0.0, Float::INFINITY).unwrap().tangent.unwrap();
+        assert_close(tangent, Vector::new(-1.0, 0.0, 0.0));
+        assert_close(bitangent, Vector::new(0.0, 1.0, 0.0));
+
+        let ray = Ray {
+            origin: Vector::new(0.25, 0.25, -1.0),
+            direction: Vector::new(0.0, 0.0, 1.0),
+        };
+        let hit = mesh.intersect(&ray, 0.0, Float::INFINITY).unwrap();
+        let (tangent, bitangent) = hit.tangent.unwrap();
+        assert_close(hit.normal, Vector::new(0.0, 0.0, -1.0));
+        assert_close(tangent, Vector::new(1.0, 0.0, 0.0));
+        assert_close(bitangent, Vector::new(0.0, -1.0, 0.0));
+    }
+
+    #[test]
+    fn empty_meshes_sample_with_zero_pdf() {
+        let mesh = TriangleMesh::new(Vec::new(), Vec::new(), MaterialId::default());
+        assert_eq!(mesh.sample_surface(0.5, 0.5).pdf, 0.0);
+        assert!(mesh.intersect(&Ray { origin: Vector::zero(), direction: Vector::new(0.0, 0.0, 1.0) }, 0.0, Float::INFINITY).is_none());
+    }
+
+    #[test]
+    fn rejects_bad_indices_and_attribute_lengths() {
+        let positions = vec![Vector::zero(), Vector::new(1.0, 0.0, 0.0), Vector::new(0.0, 1.0, 0.0)];
+        let mesh = || TriangleMesh::new(positions.clone(), vec![[0, 1, 2]], MaterialId::default());
+        assert_eq!(mesh().with_uvs(vec![[0.0, 0.0]; 3]).validate(), Ok(()));
+
+        let normals = mesh().with_normals(vec![Vector::new(0.0, 0.0, 1.0); 2]).validate().unwrap_err();
+        assert_eq!(normals.to_string(), "mesh has 3 vertices but 2 normals");
+        let mut tangents = mesh();
+        tangents.tangents = Some(vec![Vector::new(1.0, 0.0, 0.0); 4]);
+        assert_eq!(
+            tangents.validate(),
+            Err(MeshError::AttributeLength {
+                attribute: "tangents",
+                count: 4,
+                vertex_count: 3
+            })
+        );
+        let out_of_range = TriangleMesh::new(positions.clone(), vec![[0, 1, 2], [2, 1, 3]], MaterialId::default());
+        assert_eq!(
+            out_of_range.validate(),
+            Err(MeshError::IndexOutOfRange {
+                triangle: 1,
+                index: 3,
+                vertex_count: 3
+            })
+        );
+    }
+}
@@ -0,0 +1,691 @@
+//! Wavefront OBJ and MTL import.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use image::ImageError;
+
+use crate::material::{Dielectric, Dissolve, Lambertian, Material, MaterialId, Phong, Textured};
+use crate::math::Float;
+use crate::mesh::TriangleMesh;
+use crate::scene::Scene;
+use crate::texture::ImageTexture;
+use crate::vector::Vector;
+
+#[derive(Debug)]
+pub enum ObjError {
+    Io { path: PathBuf, source: io::Error },
+    Parse { path: PathBuf, line: usize, message: String },
+    Texture { path: PathBuf, source: ImageError },
+}
+
+impl fmt::Display for ObjError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ObjError::Io { path, source } => write!(f, "{}: {}", path.display(), source),
+            ObjError::Parse { path, line, message } => write!(f, "{}:{}: {}", path.display(), line, message),
+            ObjError::Texture { path, source } => write!(f, "{}: {}", path.display(), source),
+        }
+    }
+}
+
+impl std::error::Error for ObjError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ObjError::Io { source, .. } => Some(source),
+            ObjError::Parse { .. } => None,
+            ObjError::Texture { source, .. } => Some(source),
+        }
+    }
+}
+
+pub struct ObjMaterial {
+    pub name: String,
+    /// `Kd`, in `[0, 1]`.
+    pub diffuse: Vector,
+    /// `Ks`, in `[0, 1]`.
+    pub specular: Vector,
+    /// `Ns`.
+    pub shininess: Float,
+    /// `Ni`, or 1.5, typical of glass, when the material leaves it out.
+    pub ior: Float,
+    /// `d`, or `1 - Tr`; the opacity of materials that do not refract.
+    pub dissolve: Float,
+    /// `illum`; models 4, 6 and 7 refract.
+    pub illumination: u32,
+    /// `map_Kd`, multiplied by `diffuse`.
+    pub diffuse_map: Option<PathBuf>,
+    /// `map_Bump`, a height map.
+    pub bump_map: Option<PathBuf>,
+    /// The bump map's `-bm` option.
+    pub bump_multiplier: Float,
+}
+
+impl ObjMaterial {
+    /// Refracting illumination models become glass tinted by `Kd`, those with a specular color
+    /// Blinn-Phong, and everything else diffuse. Texture maps are loaded here and wrap the
+    /// material in `Textured`, and a dissolve below 1 makes any but glass partly transparent.
+    pub fn add_to_scene(&self, scene: &mut Scene) -> Result<MaterialId, ObjError> {
+        let glass = matches!(self.illumination, 4 | 6 | 7);
+        let material: Box<dyn Material> = if glass {
+            Box::new(Dielectric {
+                ior: self.ior,
+                tint: self.diffuse,
+            })
+        } else if self.specular.max_component() > 0.0 {
+            Box::new(Phong {
+                color: self.diffuse,
+                specular_color: self.specular,
+                ambient: 0.0,
+                diffuse: 1.0,
+                specular: 1.0,
+                shininess: self.shininess,
+            })
+        } else {
+            Box::new(Lambertian { albedo: self.diffuse })
+        };
+        let material: Box<dyn Material> = if self.diffuse_map.is_none() && self.bump_map.is_none() {
+            material
+        } else {
+            let load = |path: &PathBuf, srgb: bool| {
+                ImageTexture::load(path, srgb).map_err(|source| ObjError::Texture {
+                    path: path.clone(),
+                    source,
+                })
+            };
+            Box::new(Textured {
+                material,
+                color: self.diffuse_map.as_ref().map(|path| load(path, true)).transpose()?,
+                tint: self.diffuse,
+                normal: None,
+                normal_scale: 1.0,
+                bump: self.bump_map.as_ref().map(|path| load(path, false)).transpose()?,
+                bump_scale: self.bump_multiplier,
+            })
+        };
+        if self.dissolve < 1.0 && !glass {
+            return Ok(scene.add_material(Dissolve {
+                material,
+                opacity: self.dissolve.max(0.0),
+            }));
+        }
+        Ok(scene.add_boxed_material(material))
+    }
+
+    fn new(name: &str) -> ObjMaterial {
+        ObjMaterial {
+            name: name.to_string(),
+            diffuse: Vector::splat(0.8),
+            specular: Vector::zero(),
+            shininess: 0.0,
+            ior: 1.5,
+            dissolve: 1.0,
+            illumination: 2,
+            diffuse_map: None,
+            bump_map: None,
+            bump_multiplier: 1.0,
+        }
+    }
+}
+
+/// Faces sharing a group name and material, as one mesh.
+pub struct ObjGroup {
+    pub name: String,
+    pub material: Option<usize>,
+    pub mesh: TriangleMesh,
+}
+
+pub struct ObjModel {
+    pub groups: Vec<ObjGroup>,
+    pub materials: Vec<ObjMaterial>,
+    /// Problems that did not stop the import: material libraries that could not be read and
+    /// unknown material names, whose faces get the scene's default material.
+    pub warnings: Vec<ObjError>,
+}
+
+impl ObjModel {
+    /// Adds the materials and then every group to the scene. Groups without a material get
+    /// the scene's default. Fails if a texture map cannot be loaded.
+    pub fn add_to_scene(self, scene: &mut Scene) -> Result<(), ObjError> {
+        let ids = self.materials.iter().map(|material| material.add_to_scene(scene)).collect::<Result<Vec<MaterialId>, ObjError>>()?;
+        for mut group in self.groups {
+            if let Some(material) = group.material {
+                group.mesh.material = ids[material];
+            }
+            scene.add(group.mesh);
+        }
+        Ok(())
+    }
+}
+
+pub fn load_obj(path: impl AsRef<Path>) -> Result<ObjModel, ObjError> {
+    let path = path.as_ref();
+    parse_obj(&read(path)?, ObjParser::new(path))
+}
+
+fn parse_obj(source: &str, mut parser: ObjParser) -> Result<ObjModel, ObjError> {
+    for (index, line) in source.lines().enumerate() {
+        parser.line = index + 1;
+        parser.parse_line(line).map_err(|message| parser.error(message))?;
+    }
+    Ok(parser.finish())
+}
+
+pub fn load_mtl(path: impl AsRef<Path>) -> Result<Vec<ObjMaterial>, ObjError> {
+    let path = path.as_ref();
+    parse_mtl(&read(path)?, path)
+}
+
+fn parse_mtl(source: &str, path: &Path) -> Result<Vec<ObjMaterial>, ObjError> {
+    let directory = path.parent().unwrap_or(Path::new(""));
+    let mut materials: Vec<ObjMaterial> = Vec::new();
+    for (index, line) in source.lines().enumerate() {
+        parse_mtl_line(line, directory, &mut materials).map_err(|message| ObjError::Parse {
+            path: path.to_path_buf(),
+            line: index + 1,
+            message,
+        })?;
+    }
+    Ok(materials)
+}
+
+fn read(path: &Path) -> Result<String, ObjError> {
+    fs::read_to_string(path).map_err(|source| ObjError::Io {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+fn parse_floats<const N: usize>(arguments: &[&str], directive: &str) -> Result<[Float; N], String> {
+    let mut values = [0.0; N];
+    if arguments.len() < N {
+        return Err(format!("'{directive}' expects {N} numbers, found {}", arguments.len()));
+    }
+    for (value, argument) in values.iter_mut().zip(arguments) {
+        *value = argument.parse().map_err(|_| format!("invalid number '{argument}' in '{directive}'"))?;
+    }
+    Ok(values)
+}
+
+/// A texture map option, such as `-bm`, with its values.
+type MapOption<'a> = (&'a str, &'a [&'a str]);
+
+/// Splits a texture map's arguments into its options, each with its values, and the file name
+/// after them. The name may contain spaces.
+fn parse_map<'a>(arguments: &'a [&'a str], directive: &str) -> Result<(Vec<MapOption<'a>>, String), String> {
+    let mut options = Vec::new();
+    let mut rest = arguments;
+    while let Some((&option, values)) = rest.split_first() {
+        let count = match option {
+            "-blendu" | "-blendv" | "-bm" | "-boost" | "-cc" | "-clamp" | "-imfchan" | "-texres" | "-type" => 1,
+            "-mm" => 2,
+            // Offset, scale and turbulence take one to three numbers.
+            "-o" | "-s" | "-t" => values.iter().take(3).take_while(|value| value.parse::<Float>().is_ok()).count().max(1),
+            _ => break,
+        };
+        if values.len() < count {
+            return Err(format!("'{option}' without a value in '{directive}'"));
+        }
+        options.push((option, &values[..count]));
+        rest = &values[count..];
+    }
+    if rest.is_empty() {
+        return Err(format!("'{directive}' without a file name"));
+    }
+    Ok((options, rest.join(" ")))
+}
+
+fn parse_mtl_line(line: &str, directory: &Path, materials: &mut Vec<ObjMaterial>) -> Result<(), String> {
+    let tokens = line.split('#').next().unwrap_or("").split_whitespace().collect::<Vec<&str>>();
+    let Some((&directive, arguments)) = tokens.split_first() else {
+        return Ok(());
+    };
+    if directive == "newmtl" {
+        let name = arguments.join(" ");
+        if name.is_empty() {
+            return Err("'newmtl' without a name".to_string());
+        }
+        materials.push(ObjMaterial::new(&name));
+        return Ok(());
+    }
+
+    let material = materials.last_mut().ok_or_else(|| format!("'{directive}' before any 'newmtl'"))?;
+    let map_path = |name: &str| directory.join(name);
+    match directive {
+        "Kd" => material.diffuse = Vector::from(parse_floats::<3>(arguments, directive)?),
+        "Ks" => material.specular = Vector::from(parse_floats::<3>(arguments, directive)?),
+        "Ns" => material.shininess = parse_floats::<1>(arguments, directive)?[0],
+        "Ni" => material.ior = parse_floats::<1>(arguments, directive)?[0],
+        "d" => material.dissolve = parse_floats::<1>(arguments, directive)?[0],
+        "Tr" => material.dissolve = 1.0 - parse_floats::<1>(arguments, directive)?[0],
+        "illum" => {
+            let value = arguments.first().ok_or("'illum' without a value")?;
+            material.illumination = value.parse().map_err(|_| format!("invalid illumination model '{value}'"))?;
+        }
+        "map_Kd" => material.diffuse_map = Some(map_path(&parse_map(arguments, directive)?.1)),
+        "map_Bump" | "map_bump" | "bump" => {
+            let (options, name) = parse_map(arguments, directive)?;
+            material.bump_map = Some(map_path(&name));
+            if let Some((_, values)) = options.iter().find(|(option, _)| *option == "-bm") {
+                material.bump_multiplier = parse_floats::<1>(values, directive)?[0];
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+#[derive(Default)]
+struct GroupBuilder {
+    positions: Vec<Vector>,
+    uvs: Vec<[Float; 2]>,
+    normals: Vec<Vector>,
+    indices: Vec<[u32; 3]>,
+    vertex_lookup: HashMap<(usize, Option<usize>, Option<usize>), u32>,
+    missing_uvs: bool,
+    missing_normals: bool,
+}
+
+struct ObjParser<'a> {
+    path: &'a Path,
+    line: usize,
+    positions: Vec<Vector>,
+    uvs: Vec<[Float; 2]>,
+    normals: Vec<Vector>,
+    materials: Vec<ObjMaterial>,
+    group_name: String,
+    material: Option<usize>,
+    groups: Vec<(String, Option<usize>, GroupBuilder)>,
+    warnings: Vec<ObjError>,
+}
+
+impl<'a> ObjParser<'a> {
+    fn new(path: &'a Path) -> ObjParser<'a> {
+        ObjParser {
+            path,
+            line: 0,
+            positions: Vec::new(),
+            uvs: Vec::new(),
+            normals: Vec::new(),
+            materials: Vec::new(),
+            group_name: "default".to_string(),
+            material: None,
+            groups: Vec::new(),
+            warnings: Vec::new(),
+        }
+    }
+
+    fn error(&self, message: String) -> ObjError {
+        ObjError::Parse {
+            path: self.path.to_path_buf(),
+            line: self.line,
+            message,
+        }
+    }
+
+    fn parse_line(&mut self, line: &str) -> Result<(), String> {
+        let tokens = line.split('#').next().unwrap_or("").split_whitespace().collect::<Vec<&str>>();
+        let Some((&directive, arguments)) = tokens.split_first() else {
+            return Ok(());
+        };
+        match directive {
+            "v" => self.positions.push(Vector::from(parse_floats::<3>(arguments, directive)?)),
+            "vt" => {
+                let [u] = parse_floats::<1>(arguments, directive)?;
+                let v = if arguments.len() > 1 { parse_floats::<2>(arguments, directive)?[1] } else { 0.0 };
+                self.uvs.push([u, v]);
+            }
+            "vn" => self.normals.push(Vector::from(parse_floats::<3>(arguments, directive)?).normalize()),
+            "f" => self.parse_face(arguments)?,
+            "g" | "o" => self.group_name = if arguments.is_empty() { "default".to_string() } else { arguments.join(" ") },
+            // Unknown materials fall back to the scene's default with a warning, as exporters
+            // often leave the library out.
+            "usemtl" => {
+                let name = arguments.join(" ");
+                self.material = self.materials.iter().position(|material| material.name == name);
+                if self.material.is_none() {
+                    self.warnings.push(self.error(format!("unknown material '{name}'")));
+                }
+            }
+            // A library that cannot be read is skipped with a warning; one that does not parse
+            // is an error.
+            "mtllib" => {
+                let directory = self.path.parent().unwrap_or(Path::new(""));
+                for library in arguments {
+                    match load_mtl(directory.join(library)) {
+                        Ok(materials) => self.materials.extend(materials),
+                        Err(warning @ ObjError::Io { .. }) => self.warnings.push(warning),
+                        Err(error) => return Err(format!("in material library: {error}")),
+                    }
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn resolve_index(index: &str, count: usize, kind: &str) -> Result<usize, String> {
+        let value = index.parse::<i64>().map_err(|_| format!("invalid {kind} index '{index}'"))?;
+        // Negative indices count back from the most recently defined element.
+        let resolved = if value < 0 { count as i64 + value } else { value - 1 };
+        if value == 0 || resolved < 0 || resolved >= count as i64 {
+            return Err(format!("{kind} index {value} out of range (1..={count})"));
+        }
+        Ok(resolved as usize)
+    }
+
+    fn parse_face(&mut self, arguments: &[&str]) -> Result<(), String> {
+        if arguments.len() < 3 {
+            return Err(format!("face needs at least 3 vertices, found {}", arguments.len()));
+        }
+        let mut corners = Vec::with_capacity(arguments.len());
+        for argument in arguments {
+            let mut parts = argument.split('/');
+            let position = Self::resolve_index(parts.next().unwrap_or(""), self.positions.len(), "vertex")?;
+            let uv = match parts.next() {
+                Some(index) if !index.is_empty() => Some(Self::resolve_index(index, self.uvs.len(), "texture coordinate")?),
+                _ => None,
+            };
+            let normal = match parts.next() {
+                Some(index) if !index.is_empty() => Some(Self::resolve_index(index, self.normals.len(), "normal")?),
+                _ => None,
+            };
+            corners.push((position, uv, normal));
+        }
+
+        let polygon = corners.iter().map(|(position, _, _)| self.positions[*position]).collect::<Vec<Vector>>();
+        let triangles = triangulate(&polygon);
+
+        let index = self.current_group();
+        let group = &mut self.groups[index].2;
+        let vertices = corners
+            .iter()
+            .map(|&(position, uv, normal)| {
+                let key = (position, uv, normal);
+                if let Some(&vertex) = group.vertex_lookup.get(&key) {
+                    return vertex;
+                }
+                let vertex = group.positions.len() as u32;
+                group.positions.push(self.positions[position]);
+                match uv {
+                    Some(uv) => group.uvs.push(self.uvs[uv]),
+                    None => {
+                        group.uvs.push([0.0, 0.0]);
+                        group.missing_uvs = true;
+                    }
+                }
+                match normal {
+                    Some(normal) => group.normals.push(self.normals[normal]),
+                    None => {
+                        group.normals.push(Vector::zero());
+                        group.missing_normals = true;
+                    }
+                }
+                group.vertex_lookup.insert(key, vertex);
+                vertex
+            })
+            .collect::<Vec<u32>>();
+        group.indices.extend(triangles.iter().map(|[a, b, c]| [vertices[*a], vertices[*b], vertices[*c]]));
+        Ok(())
+    }
+
+    fn current_group(&mut self) -> usize {
+        let position = self.groups.iter().position(|(name, material, _)| *name == self.group_name && *material == self.material);
+        position.unwrap_or_else(|| {
+            self.groups.push((self.group_name.clone(), self.material, GroupBuilder::default()));
+            self.groups.len() - 1
+        })
+    }
+
+    fn finish(self) -> ObjModel {
+        let materials = self.materials;
+        let groups = self
+            .groups
+            .into_iter()
+            .filter(|(_, _, group)| !group.indices.is_empty())
+            .map(|(name, material, group)| {
+                let mut mesh = TriangleMesh::new(group.positions, group.indices, MaterialId::default());
+                if !group.missing_normals {
+                    mesh.normals = Some(group.normals);
+                }
+                if !group.missing_uvs {
+                    mesh.uvs = Some(group.uvs);
+                    mesh.compute_tangents();
+                }
+                ObjGroup { name, material, mesh }
+            })
+            .collect();
+        ObjModel {
+            groups,
+            materials,
+            warnings: self.warnings,
+        }
+    }
+}
+
+/// Ear-clipping triangulation of a planar polygon, returning corner indices. Falls back to a
+/// fan when the polygon is degenerate or self-intersecting.
+pub(crate) fn triangulate(polygon: &[Vector]) -> Vec<[usize; 3]> {
+    let fan = || (1..polygon.len() - 1).map(|i| [0, i, i + 1]).collect::<Vec<[usize; 3]>>();
+    if polygon.len() == 3 {
+        return vec![[0, 1, 2]];
+    }
+
+    // Project onto the plane of the Newell normal's dominant axis.
+    let mut normal = Vector::zero();
+    for (i, current) in polygon.iter().enumerate() {
+        let next = polygon[(i + 1) % polygon.len()];
+        normal += Vector::new(
+            (current.y - next.y) * (current.z + next.z),
+            (current.z - next.z) * (current.x + next.x),
+            (current.x - next.x) * (current.y + next.y),
+        );
+    }
+    let dominant = normal.abs();
+    let (a, b, sign) = if dominant.x >= dominant.y && dominant.x >= dominant.z {
+        (1, 2, normal.x.signum())
+    } else if dominant.y >= dominant.z {
+        (2, 0, normal.y.signum())
+    } else {
+        (0, 1, normal.z.signum())
+    };
+    if normal.is_near_zero() {
+        return fan();
+    }
+    let points = polygon.iter().map(|p| (p[a], p[b] * sign)).collect::<Vec<(Float, Float)>>();
+    let cross = |o: (Float, Float), p: (Float, Float), q: (Float, Float)| (p.0 - o.0) * (q.1 - o.1) - (p.1 - o.1) * (q.0 - o.0);
+
+    let mut remaining = (0..polygon.len()).collect::<Vec<usize>>();
+    let mut triangles = Vec::with_capacity(polygon.len() - 2);
+    while remaining.len() > 3 {
+        let n = remaining.len();
+        let ear = (0..n).find(|&i| {
+            let (prev, current, next) = (remaining[(i + n - 1) % n], remaining[i], remaining[(i + 1) % n]);
+            let (p, c, q) = (points[prev], points[current], points[next]);
+            if cross(p, c, q) <= 0.0 {
+                return false;
+            }
+            remaining.iter().all(|&other| {
+                if other == prev || other == current || other == next {
+                    return true;
+                }
+                let o = points[other];
+                cross(p, c, o) < 0.0 || cross(c, q, o) < 0.0 || cross(q, p, o) < 0.0
+            })
+        });
+        let Some(ear) = ear else {
+            return fan();
+        };
+        triangles.push([remaining[(ear + n - 1) % n], remaining[ear], remaining[(ear + 1) % n]]);
+        remaining.remove(ear);
+    }
+    triangles.push([remaining[0], remaining[1], remaining[2]]);
+    triangles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hittable::HitRecord;
+    use crate::math::consts::PI;
+    use crate::ray::Ray;
+
+    const MTL: &str = "newmtl red\nKd 1 0 0\n\nnewmtl glass\nillum 7\n\nnewmtl water\nillum 4\nKd 0.5 0.8 1\nNi 1.33\n\nnewmtl decal\nKd 0 0 1\nd 0.99\n";
+
+    fn parse(source: &str) -> Result<ObjModel, ObjError> {
+        let mut parser = ObjParser::new(Path::new("test.obj"));
+        parser.materials = parse_mtl(MTL, Path::new("test.mtl")).unwrap();
+        parse_obj(source, parser)
+    }
+
+    fn group_area(group: &ObjGroup) -> Float {
+        (0..group.mesh.triangle_count()).map(|triangle| group.mesh.triangle_area(triangle)).sum()
+    }
+
+    #[test]
+    fn resolves_negative_indices() {
+        let model = parse("v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nf -4 -3 -2 -1\n").unwrap();
+        assert_eq!(model.groups.len(), 1);
+        let mesh = &model.groups[0].mesh;
+        assert_eq!(mesh.triangle_count(), 2);
+        assert_eq!(mesh.positions.len(), 4);
+        assert!((group_area(&model.groups[0]) - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn triangulates_concave_polygons() {
+        // The dent at (2, 1) makes a fan from the first corner overlap itself.
+        let model = parse("v 0 0 0\nv 4 0 0\nv 4 4 0\nv 2 1 0\nv 0 4 0\nf 1 2 3 4 5\n").unwrap();
+        let group = &model.groups[0];
+        assert_eq!(group.mesh.triangle_count(), 3);
+        assert!((group_area(group) - 10.0).abs() < 1e-9);
+        for [i0, i1, i2] in &group.mesh.indices {
+            let [p0, p1, p2] = [i0, i1, i2].map(|index| group.mesh.positions[*index as usize]);
+            assert!((p1 - p0).cross(&(p2 - p0)).z > 0.0);
+        }
+    }
+
+    #[test]
+    fn splits_groups_by_name_and_material() {
+        let model = parse("v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\ng lid\nusemtl red\nf 1 2 3\nusemtl glass\nf 3 2 1\ng base\nf 1 3 2\n").unwrap();
+        let groups = model.groups.iter().map(|group| (group.name.as_str(), group.material)).collect::<Vec<_>>();
+        assert_eq!(groups, [("default", None), ("lid", Some(0)), ("lid", Some(1)), ("base", Some(1))]);
+    }
+
+    #[test]
+    fn reports_line_numbers() {
+        let error = parse("v 0 0 0\nv 1 0 0\n\nf 1 2 3\n").err().unwrap();
+        assert!(matches!(error, ObjError::Parse { line: 4, .. }));
+        assert_eq!(error.to_string(), "test.obj:4: vertex index 3 out of range (1..=2)");
+    }
+
+    #[test]
+    fn falls_back_to_the_default_material() {
+        let model = parse("mtllib missing.mtl\nv 0 0 0\nv 1 0 0\nv 0 1 0\nusemtl red\nf 1 2 3\nusemtl missing\nf 3 2 1\n").unwrap();
+        let groups = model.groups.iter().map(|group| group.material).collect::<Vec<_>>();
+        assert_eq!(groups, [Some(0), None]);
+
+        // Both the missing library and the missing material are reported.
+        assert_eq!(model.warnings.len(), 2);
+        assert!(matches!(&model.warnings[0], ObjError::Io { path, source } if path == Path::new("missing.mtl") && source.kind() == io::ErrorKind::NotFound));
+        assert_eq!(model.warnings[1].to_string(), "test.obj:7: unknown material 'missing'");
+    }
+
+    #[test]
+    fn refracting_illumination_models_become_glass() {
+        let materials = parse_mtl(MTL, Path::new("test.mtl")).unwrap();
+        assert_eq!(materials[1].ior, 1.5);
+        assert_eq!(materials[2].ior, 1.33);
+
+        let mut scene = Scene::new();
+        let ray = Ray {
+            origin: Vector::new(0.0, 0.0, 1.0),
+            direction: Vector::new(0.0, 0.0, -1.0),
+        };
+        let hit = HitRecord {
+            t: 1.0,
+            point: Vector::zero(),
+            normal: Vector::new(0.0, 0.0, 1.0),
+            front_face: true,
+            u: 0.0,
+            v: 0.0,
+            material: MaterialId::default(),
+            vertex_color: None,
+            tangent: None,
+        };
+        let specular = |material: &ObjMaterial, scene: &mut Scene| {
+            let id = material.add_to_scene(scene).unwrap();
+            scene.material(id).specular(&ray, &hit)
+        };
+        // Glass keeps `Kd` as its tint.
+        let water = specular(&materials[2], &mut scene);
+        assert!(water.refracted.is_some());
+        assert!((water.transmittance - Vector::new(0.5, 0.8, 1.0) * (1.0 - water.reflectance.x)).length() < 1e-6);
+        // Dissolve lets the rest of the light straight through instead of refracting it.
+        let decal = specular(&materials[3], &mut scene);
+        assert_eq!(decal.refracted, Some(ray.direction));
+        assert!((decal.transmittance - Vector::splat(0.01)).length() < 1e-6);
+
+        let error = parse_mtl("Kd 1 1 1\n", Path::new("test.mtl")).err().unwrap();
+        assert_eq!(error.to_string(), "test.mtl:1: 'Kd' before any 'newmtl'");
+    }
+
+    #[test]
+    fn map_names_follow_their_options_and_may_contain_spaces() {
+        let source = "newmtl brick\nmap_Kd -s 2 2 -clamp on old brick.png\nmap_Bump -mm 0 1 -bm 0.5 -o 0.5 brick bump.png\n";
+        let materials = parse_mtl(source, Path::new("textures/brick.mtl")).unwrap();
+        assert_eq!(materials[0].diffuse_map.as_deref(), Some(Path::new("textures/old brick.png")));
+        assert_eq!(materials[0].bump_map.as_deref(), Some(Path::new("textures/brick bump.png")));
+        assert_eq!(materials[0].bump_multiplier, 0.5);
+
+        let error = parse_mtl("newmtl brick\nmap_Kd -bm 0.5\n", Path::new("test.mtl")).err().unwrap();
+        assert_eq!(error.to_string(), "test.mtl:2: 'map_Kd' without a file name");
+    }
+
+    #[test]
+    fn texture_maps_reach_the_shaded_surface() {
+        let directory = std::env::temp_dir().join(format!("raytrace-obj-{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        image::RgbImage::from_raw(2, 1, vec![255, 255, 0, 0, 255, 255]).unwrap().save(directory.join("color.png")).unwrap();
+        // Height rises with u, one third per texel.
+        image::GrayImage::from_raw(3, 1, vec![0, 128, 255]).unwrap().save(directory.join("ramp.png")).unwrap();
+        let source = "newmtl painted\nKd 0.5 0.5 0.5\nmap_Kd color.png\n\nnewmtl bumpy\nKd 1 1 1\nmap_Bump -bm 2 ramp.png\n";
+        fs::write(directory.join("maps.mtl"), source).unwrap();
+        let materials = load_mtl(directory.join("maps.mtl"));
+        let mut scene = Scene::new();
+        let ids = materials.and_then(|materials| materials.iter().map(|material| material.add_to_scene(&mut scene)).collect::<Result<Vec<_>, _>>());
+        fs::remove_dir_all(&directory).unwrap();
+        let ids = ids.unwrap();
+
+        let up = Vector::new(0.0, 0.0, 1.0);
+        let at = |u: Float, tangent: Option<(Vector, Vector)>| HitRecord {
+            t: 1.0,
+            point: Vector::zero(),
+            normal: up,
+            front_face: true,
+            u,
+            v: 0.5,
+            material: MaterialId::default(),
+            vertex_color: None,
+            tangent,
+        };
+        // The texel times `Kd`, as a Lambertian albedo.
+        let painted = scene.material(ids[0]);
+        let left = painted.evaluate(&at(0.25, None), &up, &up) * PI;
+        assert!((left - Vector::new(0.5, 0.5, 0.0)).length() < 1e-6, "{left:?}");
+        let right = painted.evaluate(&at(0.75, None), &up, &up) * PI;
+        assert!((right - Vector::new(0.0, 0.5, 0.5)).length() < 1e-6, "{right:?}");
+
+        // The slope of 1/2 per texel, doubled by `-bm`, tilts the normal 45 degrees away from +u.
+        let bumpy = scene.material(ids[1]);
+        let frame = Some((Vector::new(1.0, 0.0, 0.0), Vector::new(0.0, 1.0, 0.0)));
+        let tilted = Vector::new(-1.0, 0.0, 1.0).normalize();
+        assert!((bumpy.evaluate(&at(0.5, frame), &up, &tilted).x * PI - 1.0).abs() < 1e-6);
+        assert!((bumpy.evaluate(&at(0.5, frame), &up, &up).x * PI - tilted.z).abs() < 1e-6);
+        // Without a tangent frame there is nothing to perturb.
+        assert!((bumpy.evaluate(&at(0.5, None), &up, &up).x * PI - 1.0).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,104 @@
+use crate::aabb::Aabb;
+use crate::hittable::{HitRecord, Hittable, SurfaceSample};
+use crate::material::MaterialId;
+use crate::math::Float;
+use crate::ray::Ray;
+use crate::vector::Vector;
+
+/// Infinite plane through `point`; UVs are world-space distances along the plane.
+pub struct Plane {
+    pub point: Vector,
+    pub normal: Vector,
+    pub material: MaterialId,
+}
+
+impl Hittable for Plane {
+    fn intersect(&self, ray: &Ray, t_min: Float, t_max: Float) -> Option<HitRecord> {
+        let normal = self.normal.normalize();
+        let denominator = normal.dot(&ray.direction);
+        if denominator.abs() < 1e-12 {
+            return None;
+        }
+        let t = (self.point - ray.origin).dot(&normal) / denominator;
+        if t <= t_min || t >= t_max {
+            return None;
+        }
+
+        let point = ray.origin + ray.direction * t;
+        let (tangent, bitangent) = normal.orthonormal_basis();
+        let local = point - self.point;
+        let (normal, front_face) = HitRecord::face_normal(ray, normal);
+        Some(HitRecord {
+            t,
+            point,
+            normal,
+            front_face,
+            u: local.dot(&tangent),
+            v: local.dot(&bitangent),
+            material: self.material,
+            vertex_color: None,
+            tangent: None,
+        })
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        Aabb::infinite()
+    }
+
+    /// An infinite plane has no finite area, so samples carry a zero pdf.
+    fn sample_surface(&self, _u: Float, _v: Float) -> SurfaceSample {
+        SurfaceSample {
+            point: self.point,
+            normal: self.normal.normalize(),
+            pdf: 0.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plane() -> Plane {
+        Plane {
+            point: Vector::new(0.0, 1.0, 0.0),
+            normal: Vector::new(0.0, 2.0, 0.0),
+            material: MaterialId::default(),
+        }
+    }
+
+    fn hit(origin: Vector, direction: Vector) -> Option<HitRecord> {
+        plane().intersect(&Ray { origin, direction }, 0.0, Float::INFINITY)
+    }
+
+    #[test]
+    fn hits_with_unit_normal_facing_the_ray() {
+        // `t` is measured in multiples of the unnormalized direction.
+        let above = hit(Vector::new(3.0, 5.0, -2.0), Vector::new(0.0, -2.0, 0.0)).unwrap();
+        assert_eq!((above.t, above.normal, above.front_face), (2.0, Vector::new(0.0, 1.0, 0.0), true));
+        assert_eq!(above.point, Vector::new(3.0, 1.0, -2.0));
+
+        let below = hit(Vector::new(0.0, -3.0, 0.0), Vector::new(0.0, 1.0, 0.0)).unwrap();
+        assert_eq!((below.t, below.normal, below.front_face), (4.0, Vector::new(0.0, -1.0, 0.0), false));
+    }
+
+    #[test]
+    fn uvs_are_distances_along_the_plane() {
+        let down = Vector::new(0.0, -1.0, 0.0);
+        let origin = hit(Vector::new(0.0, 2.0, 0.0), down).unwrap();
+        assert_eq!((origin.u, origin.v), (0.0, 0.0));
+        let offset = hit(Vector::new(3.0, 2.0, -4.0), down).unwrap();
+        assert!((offset.u.hypot(offset.v) - 5.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn misses_parallel_and_receding_rays() {
+        assert!(hit(Vector::new(0.0, 2.0, 0.0), Vector::new(1.0, 0.0, 0.0)).is_none());
+        assert!(hit(Vector::new(0.0, 2.0, 0.0), Vector::new(0.0, 1.0, 0.0)).is_none());
+        let ray = Ray {
+            origin: Vector::new(0.0, 5.0, 0.0),
+            direction: Vector::new(0.0, -1.0, 0.0),
+        };
+        assert!(plane().intersect(&ray, 0.0, 3.0).is_none());
+    }
+}
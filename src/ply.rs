@@ -0,0 +1,410 @@
+//! Stanford PLY import, ASCII and binary.
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::material::MaterialId;
+use crate::math::Float;
+use crate::mesh::TriangleMesh;
+use crate::obj::triangulate;
+use crate::texture::srgb_to_linear;
+use crate::vector::Vector;
+
+#[derive(Debug)]
+pub enum PlyError {
+    Io { path: PathBuf, source: io::Error },
+    Parse { path: PathBuf, message: String },
+}
+
+impl fmt::Display for PlyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PlyError::Io { path, source } => write!(f, "{}: {}", path.display(), source),
+            PlyError::Parse { path, message } => write!(f, "{}: {}", path.display(), message),
+        }
+    }
+}
+
+impl std::error::Error for PlyError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            PlyError::Io { source, .. } => Some(source),
+            PlyError::Parse { .. } => None,
+        }
+    }
+}
+
+/// Loads the `vertex` and `face` elements of a PLY file. Vertex colors, normals and texture
+/// coordinates are kept when present; polygons are triangulated. Integer vertex colors are
+/// decoded from sRGB.
+pub fn load_ply(path: impl AsRef<Path>) -> Result<TriangleMesh, PlyError> {
+    let path = path.as_ref();
+    let bytes = fs::read(path).map_err(|source| PlyError::Io {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    parse_ply(&bytes).map_err(|message| PlyError::Parse {
+        path: path.to_path_buf(),
+        message,
+    })
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Encoding {
+    Ascii,
+    LittleEndian,
+    BigEndian,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Scalar {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    F32,
+    F64,
+}
+
+impl Scalar {
+    fn parse(name: &str) -> Result<Scalar, String> {
+        Ok(match name {
+            "char" | "int8" => Scalar::I8,
+            "uchar" | "uint8" => Scalar::U8,
+            "short" | "int16" => Scalar::I16,
+            "ushort" | "uint16" => Scalar::U16,
+            "int" | "int32" => Scalar::I32,
+            "uint" | "uint32" => Scalar::U32,
+            "float" | "float32" => Scalar::F32,
+            "double" | "float64" => Scalar::F64,
+            _ => return Err(format!("unknown property type '{name}'")),
+        })
+    }
+
+    fn size(self) -> usize {
+        match self {
+            Scalar::I8 | Scalar::U8 => 1,
+            Scalar::I16 | Scalar::U16 => 2,
+            Scalar::I32 | Scalar::U32 | Scalar::F32 => 4,
+            Scalar::F64 => 8,
+        }
+    }
+
+    /// Scale that maps the type's range to `[0, 1]` for color channels.
+    fn color_scale(self) -> f64 {
+        match self {
+            Scalar::U8 | Scalar::I8 => 1.0 / 255.0,
+            Scalar::U16 | Scalar::I16 => 1.0 / 65535.0,
+            _ => 1.0,
+        }
+    }
+}
+
+enum Property {
+    Scalar { name: String, kind: Scalar },
+    List { name: String, count: Scalar, item: Scalar },
+}
+
+impl Property {
+    fn name(&self) -> &str {
+        match self {
+            Property::Scalar { name, .. } | Property::List { name, .. } => name,
+        }
+    }
+}
+
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+/// One element instance. Lists contribute their length to `values` and their items to `lists`.
+struct Record {
+    values: Vec<f64>,
+    lists: Vec<Vec<f64>>,
+}
+
+enum Body<'a> {
+    Ascii(std::str::SplitWhitespace<'a>),
+    Binary { bytes: &'a [u8], offset: usize, big_endian: bool },
+}
+
+impl Body<'_> {
+    fn read(&mut self, kind: Scalar) -> Result<f64, String> {
+        match self {
+            Body::Ascii(tokens) => {
+                let token = tokens.next().ok_or("unexpected end of data")?;
+                token.parse::<f64>().map_err(|_| format!("invalid number '{token}'"))
+            }
+            Body::Binary { bytes, offset, big_endian } => {
+                let size = kind.size();
+                let data = bytes.get(*offset..*offset + size).ok_or("unexpected end of data")?;
+                *offset += size;
+                let mut buffer = [0u8; 8];
+                buffer[..size].copy_from_slice(data);
+                if *big_endian {
+                    buffer[..size].reverse();
+                }
+                Ok(match kind {
+                    Scalar::I8 => buffer[0] as i8 as f64,
+                    Scalar::U8 => buffer[0] as f64,
+                    Scalar::I16 => i16::from_le_bytes([buffer[0], buffer[1]]) as f64,
+                    Scalar::U16 => u16::from_le_bytes([buffer[0], buffer[1]]) as f64,
+                    Scalar::I32 => i32::from_le_bytes([buffer[0], buffer[1], buffer[2], buffer[3]]) as f64,
+                    Scalar::U32 => u32::from_le_bytes([buffer[0], buffer[1], buffer[2], buffer[3]]) as f64,
+                    Scalar::F32 => f32::from_le_bytes([buffer[0], buffer[1], buffer[2], buffer[3]]) as f64,
+                    Scalar::F64 => f64::from_le_bytes(buffer),
+                })
+            }
+        }
+    }
+
+    fn read_record(&mut self, element: &Element) -> Result<Record, String> {
+        let mut record = Record {
+            values: Vec::with_capacity(element.properties.len()),
+            lists: Vec::new(),
+        };
+        for property in &element.properties {
+            match property {
+                Property::Scalar { kind, .. } => record.values.push(self.read(*kind)?),
+                Property::List { count, item, .. } => {
+                    let length = self.read(*count)?;
+                    if length < 0.0 || length.fract() != 0.0 {
+                        return Err(format!("invalid list length {length}"));
+                    }
+                    let list = (0..length as usize).map(|_| self.read(*item)).collect::<Result<Vec<f64>, String>>()?;
+                    record.values.push(list.len() as f64);
+                    record.lists.push(list);
+                }
+            }
+        }
+        Ok(record)
+    }
+}
+
+fn parse_header(bytes: &[u8]) -> Result<(Encoding, Vec<Element>, usize), String> {
+    if !bytes.starts_with(b"ply") {
+        return Err("missing 'ply' magic number".to_string());
+    }
+    let mut encoding = None;
+    let mut elements: Vec<Element> = Vec::new();
+    let mut offset = 0;
+    for (index, line) in bytes.split(|byte| *byte == b'\n').enumerate() {
+        offset += line.len() + 1;
+        let line = std::str::from_utf8(line).map_err(|_| format!("header line {}: not valid text", index + 1))?;
+        let error = |message: String| format!("header line {}: {message}", index + 1);
+        let tokens = line.split_whitespace().collect::<Vec<&str>>();
+        match tokens.as_slice() {
+            ["ply"] | [] => {}
+            ["comment", ..] | ["obj_info", ..] => {}
+            ["format", format, _version] => {
+                encoding = Some(match *format {
+                    "ascii" => Encoding::Ascii,
+                    "binary_little_endian" => Encoding::LittleEndian,
+                    "binary_big_endian" => Encoding::BigEndian,
+                    _ => return Err(error(format!("unknown format '{format}'"))),
+                });
+            }
+            ["element", name, count] => elements.push(Element {
+                name: name.to_string(),
+                count: count.parse().map_err(|_| error(format!("invalid element count '{count}'")))?,
+                properties: Vec::new(),
+            }),
+            ["property", "list", count, item, name] => {
+                let element = elements.last_mut().ok_or_else(|| error("property before any element".to_string()))?;
+                element.properties.push(Property::List {
+                    name: name.to_string(),
+                    count: Scalar::parse(count).map_err(error)?,
+                    item: Scalar::parse(item).map_err(error)?,
+                });
+            }
+            ["property", kind, name] => {
+                let element = elements.last_mut().ok_or_else(|| error("property before any element".to_string()))?;
+                element.properties.push(Property::Scalar {
+                    name: name.to_string(),
+                    kind: Scalar::parse(kind).map_err(error)?,
+                });
+            }
+            ["end_header"] => {
+                let encoding = encoding.ok_or_else(|| error("'end_header' before 'format'".to_string()))?;
+                return Ok((encoding, elements, offset));
+            }
+            _ => return Err(error(format!("unrecognized header line '{}'", line.trim()))),
+        }
+    }
+    Err("missing 'end_header'".to_string())
+}
+
+fn parse_ply(bytes: &[u8]) -> Result<TriangleMesh, String> {
+    let (encoding, elements, header_length) = parse_header(bytes)?;
+    let data = bytes.get(header_length..).unwrap_or(&[]);
+    let mut body = match encoding {
+        Encoding::Ascii => Body::Ascii(std::str::from_utf8(data).map_err(|_| "ASCII body is not valid text")?.split_whitespace()),
+        Encoding::LittleEndian | Encoding::BigEndian => Body::Binary {
+            bytes: data,
+            offset: 0,
+            big_endian: encoding == Encoding::BigEndian,
+        },
+    };
+
+    let mut positions = Vec::new();
+    let mut normals = Vec::new();
+    let mut colors = Vec::new();
+    let mut uvs = Vec::new();
+    let mut indices = Vec::new();
+    for element in &elements {
+        let find = |names: &[&str]| element.properties.iter().position(|property| names.contains(&property.name()));
+        match element.name.as_str() {
+            "vertex" => {
+                let (Some(x), Some(y), Some(z)) = (find(&["x"]), find(&["y"]), find(&["z"])) else {
+                    return Err("vertex element without x, y and z properties".to_string());
+                };
+                let normal = find(&["nx"]).zip(find(&["ny"])).zip(find(&["nz"]));
+                let color = find(&["red", "r"]).zip(find(&["green", "g"])).zip(find(&["blue", "b"]));
+                let color_scale = color.map_or(1.0, |((red, _), _)| match &element.properties[red] {
+                    Property::Scalar { kind, .. } => kind.color_scale(),
+                    Property::List { .. } => 1.0,
+                });
+                let uv = find(&["u", "s", "texture_u", "texture_s"]).zip(find(&["v", "t", "texture_v", "texture_t"]));
+                for index in 0..element.count {
+                    let record = body.read_record(element).map_err(|message| format!("vertex {index}: {message}"))?;
+                    let vector = |(x, y, z): (usize, usize, usize)| {
+                        Vector::new(record.values[x] as Float, record.values[y] as Float, record.values[z] as Float)
+                    };
+                    positions.push(vector((x, y, z)));
+                    if let Some(((nx, ny), nz)) = normal {
+                        normals.push(vector((nx, ny, nz)).normalize());
+                    }
+                    if let Some(((red, green), blue)) = color {
+                        let color = vector((red, green, blue)) * color_scale as Float;
+                        // Integer channels are sRGB-encoded; float channels are taken as linear.
+                        colors.push(if color_scale < 1.0 {
+                            Vector::new(srgb_to_linear(color.x), srgb_to_linear(color.y), srgb_to_linear(color.z))
+                        } else {
+                            color
+                        });
+                    }
+                    if let Some((u, v)) = uv {
+                        uvs.push([record.values[u] as Float, record.values[v] as Float]);
+                    }
+                }
+            }
+            "face" => {
+                let list = element.properties.iter().filter(|property| matches!(property, Property::List { .. }));
+                let vertex_list = list
+                    .enumerate()
+                    .find(|(_, property)| matches!(property.name(), "vertex_indices" | "vertex_index"))
+                    .map(|(list_index, _)| list_index)
+                    .ok_or("face element without a vertex_indices list")?;
+                for index in 0..element.count {
+                    let record = body.read_record(element).map_err(|message| format!("face {index}: {message}"))?;
+                    let face = &record.lists[vertex_list];
+                    if face.len() < 3 {
+                        return Err(format!("face {index}: needs at least 3 vertices, found {}", face.len()));
+                    }
+                    let mut corners = Vec::with_capacity(face.len());
+                    for vertex in face {
+                        if *vertex < 0.0 || *vertex as usize >= positions.len() {
+                            return Err(format!("face {index}: vertex index {vertex} out of range (0..{})", positions.len()));
+                        }
+                        corners.push(*vertex as u32);
+                    }
+                    let polygon = corners.iter().map(|corner| positions[*corner as usize]).collect::<Vec<Vector>>();
+                    indices.extend(triangulate(&polygon).iter().map(|[a, b, c]| [corners[*a], corners[*b], corners[*c]]));
+                }
+            }
+            // Other elements (edges, materials, ...) are read and discarded.
+            _ => {
+                for index in 0..element.count {
+                    body.read_record(element).map_err(|message| format!("{} {index}: {message}", element.name))?;
+                }
+            }
+        }
+    }
+
+    let mut mesh = TriangleMesh::new(positions, indices, MaterialId::default());
+    if !normals.is_empty() {
+        mesh.normals = Some(normals);
+    }
+    if !colors.is_empty() {
+        mesh.colors = Some(colors);
+    }
+    if !uvs.is_empty() {
+        mesh.uvs = Some(uvs);
+    }
+    mesh.validate().map_err(|error| error.to_string())?;
+    mesh.compute_tangents();
+    Ok(mesh)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: Vector, expected: Vector) {
+        assert!((actual - expected).length() < 1e-5, "{actual:?} != {expected:?}");
+    }
+
+    const HEADER: &str = "ply
+format ascii 1.0
+comment a unit square as one quad
+element vertex 4
+property float x
+property float y
+property float z
+property uchar red
+property uchar green
+property uchar blue
+element face 1
+property list uchar int vertex_indices
+end_header
+";
+
+    #[test]
+    fn parses_ascii_and_decodes_integer_colors() {
+        let source = format!("{HEADER}0 0 0 255 128 0\n1 0 0 255 128 0\n1 1 0 255 128 0\n0 1 0 255 128 0\n4 0 1 2 3\n");
+        let mesh = parse_ply(source.as_bytes()).unwrap();
+        assert_eq!(mesh.positions.len(), 4);
+        assert_eq!(mesh.triangle_count(), 2);
+        assert!(((0..2).map(|triangle| mesh.triangle_area(triangle)).sum::<Float>() - 1.0).abs() < 1e-6);
+        // 128 is about 0.2159 once decoded from sRGB.
+        assert_close(mesh.colors.as_ref().unwrap()[0], Vector::new(1.0, srgb_to_linear(128.0 / 255.0), 0.0));
+        assert!((srgb_to_linear(128.0 / 255.0) - 0.2159).abs() < 1e-4);
+
+        let source = format!("{HEADER}0 0 0 0 0 0\n1 0 0 0 0 0\n1 1 0 0 0 0\n0 1 0 0 0 0\n3 0 1 4\n");
+        assert_eq!(parse_ply(source.as_bytes()).err().unwrap(), "face 0: vertex index 4 out of range (0..4)");
+    }
+
+    #[test]
+    fn parses_binary_in_both_byte_orders() {
+        for (format, big_endian) in [("binary_little_endian", false), ("binary_big_endian", true)] {
+            let mut bytes = format!(
+                "ply\nformat {format} 1.0\nelement vertex 3\nproperty float x\nproperty float y\nproperty float z\nproperty float red\n\
+                 property float green\nproperty float blue\nelement face 1\nproperty list uchar int vertex_indices\nend_header\n"
+            )
+            .into_bytes();
+            let vertices: [[f32; 6]; 3] = [[0.0, 0.0, 0.0, 0.5, 0.25, 1.0], [2.0, 0.0, 0.0, 0.5, 0.25, 1.0], [0.0, 3.0, 1.0, 0.5, 0.25, 1.0]];
+            for value in vertices.iter().flatten() {
+                bytes.extend(if big_endian { value.to_be_bytes() } else { value.to_le_bytes() });
+            }
+            bytes.push(3);
+            for index in [0i32, 1, 2] {
+                bytes.extend(if big_endian { index.to_be_bytes() } else { index.to_le_bytes() });
+            }
+
+            let mesh = parse_ply(&bytes).unwrap();
+            assert_eq!(mesh.indices, [[0, 1, 2]]);
+            assert_close(mesh.positions[2], Vector::new(0.0, 3.0, 1.0));
+            // Float colors are already linear.
+            assert_close(mesh.colors.as_ref().unwrap()[1], Vector::new(0.5, 0.25, 1.0));
+
+            bytes.truncate(bytes.len() - 2);
+            assert_eq!(parse_ply(&bytes).err().unwrap(), "face 0: unexpected end of data");
+        }
+    }
+}
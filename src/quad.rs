@@ -0,0 +1,124 @@
+use crate::aabb::Aabb;
+use crate::hittable::{HitRecord, Hittable, SurfaceSample};
+use crate::material::MaterialId;
+use crate::math::Float;
+use crate::ray::Ray;
+use crate::vector::Vector;
+
+/// Parallelogram spanned by `u` and `v` from `corner`; a rectangle when they are orthogonal.
+/// UVs run from 0 to 1 along `u` and `v`.
+pub struct Quad {
+    corner: Vector,
+    u: Vector,
+    v: Vector,
+    normal: Vector,
+    w: Vector,
+    area: Float,
+    pub material: MaterialId,
+}
+
+impl Quad {
+    /// Returns `None` if `u` and `v` are parallel or zero, which would leave no area.
+    pub fn new(corner: Vector, u: Vector, v: Vector, material: MaterialId) -> Option<Quad> {
+        let n = u.cross(&v);
+        if n.length_squared() <= 0.0 {
+            return None;
+        }
+        Some(Quad {
+            corner,
+            u,
+            v,
+            normal: n.normalize(),
+            w: n / n.length_squared(),
+            area: n.length(),
+            material,
+        })
+    }
+}
+
+impl Hittable for Quad {
+    fn intersect(&self, ray: &Ray, t_min: Float, t_max: Float) -> Option<HitRecord> {
+        let denominator = self.normal.dot(&ray.direction);
+        if denominator.abs() < 1e-12 {
+            return None;
+        }
+        let t = (self.corner - ray.origin).dot(&self.normal) / denominator;
+        if t <= t_min || t >= t_max {
+            return None;
+        }
+
+        let point = ray.origin + ray.direction * t;
+        let local = point - self.corner;
+        let alpha = self.w.dot(&local.cross(&self.v));
+        let beta = self.w.dot(&self.u.cross(&local));
+        if !(0.0..=1.0).contains(&alpha) || !(0.0..=1.0).contains(&beta) {
+            return None;
+        }
+
+        let (normal, front_face) = HitRecord::face_normal(ray, self.normal);
+        Some(HitRecord {
+            t,
+            point,
+            normal,
+            front_face,
+            u: alpha,
+            v: beta,
+            material: self.material,
+            vertex_color: None,
+            tangent: None,
+        })
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        [self.corner, self.corner + self.u, self.corner + self.v, self.corner + self.u + self.v]
+            .iter()
+            .fold(Aabb::empty(), |bounds, point| bounds.union(&Aabb::new(*point, *point)))
+            .padded(1e-4)
+    }
+
+    fn sample_surface(&self, u: Float, v: Float) -> SurfaceSample {
+        SurfaceSample {
+            point: self.corner + self.u * u + self.v * v,
+            normal: self.normal,
+            pdf: 1.0 / self.area,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quad() -> Quad {
+        Quad::new(Vector::zero(), Vector::new(2.0, 0.0, 0.0), Vector::new(0.0, 3.0, 0.0), MaterialId::default()).unwrap()
+    }
+
+    fn hit(origin: Vector, direction: Vector) -> Option<HitRecord> {
+        quad().intersect(&Ray { origin, direction }, 0.0, Float::INFINITY)
+    }
+
+    #[test]
+    fn hits_with_normal_and_uv() {
+        let front = hit(Vector::new(1.0, 1.5, 5.0), Vector::new(0.0, 0.0, -1.0)).unwrap();
+        assert_eq!((front.t, front.normal, front.front_face), (5.0, Vector::new(0.0, 0.0, 1.0), true));
+        assert_eq!((front.u, front.v), (0.5, 0.5));
+
+        let back = hit(Vector::new(0.5, 3.0, -1.0), Vector::new(0.0, 0.0, 0.5)).unwrap();
+        assert_eq!((back.t, back.normal, back.front_face), (2.0, Vector::new(0.0, 0.0, -1.0), false));
+        assert_eq!((back.u, back.v), (0.25, 1.0));
+    }
+
+    #[test]
+    fn misses_outside_and_parallel() {
+        assert!(hit(Vector::new(2.5, 1.0, 5.0), Vector::new(0.0, 0.0, -1.0)).is_none());
+        assert!(hit(Vector::new(1.0, -0.1, 5.0), Vector::new(0.0, 0.0, -1.0)).is_none());
+        assert!(hit(Vector::new(-1.0, 1.0, 0.0), Vector::new(1.0, 0.0, 0.0)).is_none());
+        assert!(hit(Vector::new(1.0, 1.0, -1.0), Vector::new(0.0, 0.0, -1.0)).is_none());
+    }
+
+    #[test]
+    fn rejects_parallel_edges() {
+        assert!(Quad::new(Vector::zero(), Vector::new(1.0, 1.0, 0.0), Vector::new(2.0, 2.0, 0.0), MaterialId::default()).is_none());
+        assert!(Quad::new(Vector::zero(), Vector::zero(), Vector::new(0.0, 1.0, 0.0), MaterialId::default()).is_none());
+    }
+}
@@ -0,0 +1,373 @@
+//! Surfaces of revolution around the local y axis whose squared radius is quadratic in height.
+//! Use an `Instance` to position, orient or stretch them.
+
+use crate::aabb::Aabb;
+use crate::hittable::{HitRecord, Hittable, SurfaceSample};
+use crate::material::MaterialId;
+use crate::math::consts::PI;
+use crate::math::{to_f64, Float};
+use crate::ray::Ray;
+use crate::solver::solve_quadratic;
+use crate::vector::Vector;
+
+/// `x^2 + z^2 = alpha + beta y + gamma y^2` for `y` in `[y_min, y_max]`, optionally closed by disks.
+struct Profile {
+    alpha: Float,
+    beta: Float,
+    gamma: Float,
+    y_min: Float,
+    y_max: Float,
+    cap_bottom: bool,
+    cap_top: bool,
+}
+
+impl Profile {
+    fn radius_squared(&self, y: Float) -> Float {
+        (self.alpha + self.beta * y + self.gamma * y * y).max(0.0)
+    }
+
+    /// Surface area per unit height and unit angle, `r(y) * sqrt(1 + r'(y)^2)`.
+    fn area_density(&self, y: Float) -> Float {
+        let r_dr = self.beta / 2.0 + self.gamma * y;
+        (self.radius_squared(y) + r_dr * r_dr).sqrt()
+    }
+
+    fn max_radius(&self) -> Float {
+        let mut r2 = self.radius_squared(self.y_min).max(self.radius_squared(self.y_max));
+        if self.gamma < 0.0 {
+            let vertex = -self.beta / (2.0 * self.gamma);
+            if vertex > self.y_min && vertex < self.y_max {
+                r2 = r2.max(self.radius_squared(vertex));
+            }
+        }
+        r2.sqrt()
+    }
+
+    fn caps(&self) -> impl Iterator<Item = (Float, Float)> + '_ {
+        [(self.cap_bottom, self.y_min, -1.0), (self.cap_top, self.y_max, 1.0)]
+            .into_iter()
+            .filter(|(enabled, _, _)| *enabled)
+            .map(|(_, y, side)| (y, side))
+    }
+
+    fn side_area(&self) -> Float {
+        const STEPS: usize = 32;
+        let dy = (self.y_max - self.y_min) / STEPS as Float;
+        (0..STEPS).map(|i| self.area_density(self.y_min + (i as Float + 0.5) * dy) * dy).sum::<Float>() * 2.0 * PI
+    }
+
+    fn intersect(&self, ray: &Ray, t_min: Float, t_max: Float, material: MaterialId) -> Option<HitRecord> {
+        let (o, d) = (ray.origin, ray.direction);
+        let a = d.x * d.x + d.z * d.z - self.gamma * d.y * d.y;
+        let b = 2.0 * (o.x * d.x + o.z * d.z) - self.beta * d.y - 2.0 * self.gamma * o.y * d.y;
+        let c = o.x * o.x + o.z * o.z - self.alpha - self.beta * o.y - self.gamma * o.y * o.y;
+
+        let mut closest: Option<(Float, Vector, Float, Float)> = None;
+        let mut t_max = t_max;
+        if let Some((t0, t1)) = solve_quadratic(to_f64(a), to_f64(b), to_f64(c)) {
+            for t in [t0 as Float, t1 as Float] {
+                if t <= t_min || t >= t_max {
+                    continue;
+                }
+                let p = o + d * t;
+                if p.y < self.y_min || p.y > self.y_max {
+                    continue;
+                }
+                let normal = Vector::new(p.x, -(self.beta / 2.0 + self.gamma * p.y), p.z).normalize();
+                let phi = p.z.atan2(p.x) + PI;
+                let v = (p.y - self.y_min) / (self.y_max - self.y_min);
+                closest = Some((t, normal, phi / (2.0 * PI), v));
+                t_max = t;
+                break;
+            }
+        }
+
+        for (y, side) in self.caps() {
+            if d.y == 0.0 {
+                continue;
+            }
+            let t = (y - o.y) / d.y;
+            if t <= t_min || t >= t_max {
+                continue;
+            }
+            let p = o + d * t;
+            let rho2 = p.x * p.x + p.z * p.z;
+            let radius2 = self.radius_squared(y);
+            if rho2 > radius2 {
+                continue;
+            }
+            let phi = p.z.atan2(p.x) + PI;
+            closest = Some((t, Vector::new(0.0, side, 0.0), phi / (2.0 * PI), (rho2 / radius2).sqrt()));
+            t_max = t;
+        }
+
+        let (t, outward_normal, u, v) = closest?;
+        let (normal, front_face) = HitRecord::face_normal(ray, outward_normal);
+        Some(HitRecord {
+            t,
+            point: o + d * t,
+            normal,
+            front_face,
+            u,
+            v,
+            material,
+            vertex_color: None,
+            tangent: None,
+        })
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        let r = self.max_radius();
+        Aabb::new(Vector::new(-r, self.y_min, -r), Vector::new(r, self.y_max, r))
+    }
+
+    /// Picks the side or a cap in proportion to area, then samples angle and height uniformly
+    /// and reports the matching area pdf.
+    fn sample_surface(&self, u: Float, v: Float) -> SurfaceSample {
+        let side_area = self.side_area();
+        let cap_areas = self.caps().map(|(y, side)| (y, side, PI * self.radius_squared(y))).collect::<Vec<_>>();
+        let total_area = side_area + cap_areas.iter().map(|(_, _, area)| area).sum::<Float>();
+        let phi = 2.0 * PI * v;
+
+        let mut remaining = u * total_area;
+        for (y, side, area) in cap_areas {
+            if remaining < area {
+                let rho = self.radius_squared(y).sqrt() * (remaining / area).sqrt();
+                return SurfaceSample {
+                    point: Vector::new(rho * phi.cos(), y, rho * phi.sin()),
+                    normal: Vector::new(0.0, side, 0.0),
+                    pdf: (area / total_area) / area,
+                };
+            }
+            remaining -= area;
+        }
+
+        let y = self.y_min + (remaining / side_area).clamp(0.0, 1.0) * (self.y_max - self.y_min);
+        let rho = self.radius_squared(y).sqrt();
+        let point = Vector::new(rho * phi.cos(), y, rho * phi.sin());
+        SurfaceSample {
+            point,
+            normal: Vector::new(point.x, -(self.beta / 2.0 + self.gamma * y), point.z).normalize(),
+            pdf: (side_area / total_area) / ((self.y_max - self.y_min) * 2.0 * PI * self.area_density(y)),
+        }
+    }
+}
+
+macro_rules! impl_hittable_for_profile {
+    ($shape:ty) => {
+        impl Hittable for $shape {
+            fn intersect(&self, ray: &Ray, t_min: Float, t_max: Float) -> Option<HitRecord> {
+                self.profile().intersect(ray, t_min, t_max, self.material)
+            }
+
+            fn bounding_box(&self) -> Aabb {
+                self.profile().bounding_box()
+            }
+
+            fn sample_surface(&self, u: Float, v: Float) -> SurfaceSample {
+                self.profile().sample_surface(u, v)
+            }
+        }
+    };
+}
+
+/// Cylinder of `radius` from `y_min` to `y_max`.
+pub struct Cylinder {
+    pub radius: Float,
+    pub y_min: Float,
+    pub y_max: Float,
+    pub capped: bool,
+    pub material: MaterialId,
+}
+
+impl Cylinder {
+    fn profile(&self) -> Profile {
+        Profile {
+            alpha: self.radius * self.radius,
+            beta: 0.0,
+            gamma: 0.0,
+            y_min: self.y_min,
+            y_max: self.y_max,
+            cap_bottom: self.capped,
+            cap_top: self.capped,
+        }
+    }
+}
+
+impl_hittable_for_profile!(Cylinder);
+
+/// Cone with its base of `radius` at `y = 0` and its apex at `y = height`.
+pub struct Cone {
+    pub radius: Float,
+    pub height: Float,
+    pub capped: bool,
+    pub material: MaterialId,
+}
+
+impl Cone {
+    fn profile(&self) -> Profile {
+        let k = self.radius * self.radius / (self.height * self.height);
+        Profile {
+            alpha: k * self.height * self.height,
+            beta: -2.0 * k * self.height,
+            gamma: k,
+            y_min: 0.0,
+            y_max: self.height,
+            cap_bottom: self.capped,
+            cap_top: false,
+        }
+    }
+}
+
+impl_hittable_for_profile!(Cone);
+
+/// Paraboloid with its vertex at the origin, opening up to `radius` at `y = height`.
+pub struct Paraboloid {
+    pub radius: Float,
+    pub height: Float,
+    pub capped: bool,
+    pub material: MaterialId,
+}
+
+impl Paraboloid {
+    fn profile(&self) -> Profile {
+        Profile {
+            alpha: 0.0,
+            beta: self.radius * self.radius / self.height,
+            gamma: 0.0,
+            y_min: 0.0,
+            y_max: self.height,
+            cap_bottom: false,
+            cap_top: self.capped,
+        }
+    }
+}
+
+impl_hittable_for_profile!(Paraboloid);
+
+/// One-sheet hyperboloid `x^2 + z^2 - (waist_radius / c)^2 y^2 = waist_radius^2`.
+pub struct Hyperboloid {
+    pub waist_radius: Float,
+    pub c: Float,
+    pub y_min: Float,
+    pub y_max: Float,
+    pub capped: bool,
+    pub material: MaterialId,
+}
+
+impl Hyperboloid {
+    fn profile(&self) -> Profile {
+        let a2 = self.waist_radius * self.waist_radius;
+        Profile {
+            alpha: a2,
+            beta: 0.0,
+            gamma: a2 / (self.c * self.c),
+            y_min: self.y_min,
+            y_max: self.y_max,
+            cap_bottom: self.capped,
+            cap_top: self.capped,
+        }
+    }
+}
+
+impl_hittable_for_profile!(Hyperboloid);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hit(shape: &dyn Hittable, origin: Vector, direction: Vector) -> Option<HitRecord> {
+        shape.intersect(&Ray { origin, direction }, 0.0, Float::INFINITY)
+    }
+
+    fn assert_hit(shape: &dyn Hittable, origin: Vector, direction: Vector, expected: Float, front_face: bool) {
+        let hit = hit(shape, origin, direction).expect("expected a hit");
+        assert!((hit.t - expected).abs() < 1e-4, "{} != {expected}", hit.t);
+        assert_eq!(hit.front_face, front_face);
+    }
+
+    /// At a tangent the ray is perpendicular to the normal, so only the distance is checked.
+    fn assert_tangent(shape: &dyn Hittable, origin: Vector, direction: Vector, expected: Float) {
+        let hit = hit(shape, origin, direction).expect("expected a hit");
+        assert!((hit.t - expected).abs() < 1e-4, "{} != {expected}", hit.t);
+    }
+
+    fn cylinder() -> Cylinder {
+        Cylinder {
+            radius: 1.0,
+            y_min: -1.0,
+            y_max: 1.0,
+            capped: true,
+            material: MaterialId::default(),
+        }
+    }
+
+    #[test]
+    fn cylinder_side_and_cap() {
+        let x = Vector::new(1.0, 0.0, 0.0);
+        assert_hit(&cylinder(), Vector::new(-5.0, 0.0, 0.0), x, 4.0, true);
+        assert_hit(&cylinder(), Vector::new(0.0, 5.0, 0.0), Vector::new(0.0, -1.0, 0.0), 4.0, true);
+        assert_hit(&cylinder(), Vector::zero(), x, 1.0, false);
+        assert_hit(&cylinder(), Vector::zero(), Vector::new(0.0, 1.0, 0.0), 1.0, false);
+    }
+
+    #[test]
+    fn cylinder_tangent_and_miss() {
+        let x = Vector::new(1.0, 0.0, 0.0);
+        assert_tangent(&cylinder(), Vector::new(-5.0, 0.0, 1.0), x, 5.0);
+        assert!(hit(&cylinder(), Vector::new(-5.0, 0.0, 1.1), x).is_none());
+        assert!(hit(&cylinder(), Vector::new(-5.0, 1.5, 0.0), x).is_none());
+    }
+
+    #[test]
+    fn uncapped_cylinder_is_open_at_the_ends() {
+        let open = Cylinder { capped: false, ..cylinder() };
+        assert!(hit(&open, Vector::new(0.0, 5.0, 0.0), Vector::new(0.0, -1.0, 0.0)).is_none());
+    }
+
+    #[test]
+    fn cone() {
+        let cone = Cone {
+            radius: 1.0,
+            height: 2.0,
+            capped: true,
+            material: MaterialId::default(),
+        };
+        let x = Vector::new(1.0, 0.0, 0.0);
+        assert_hit(&cone, Vector::new(-5.0, 1.0, 0.0), x, 4.5, true);
+        assert_hit(&cone, Vector::new(0.0, 1.0, 0.0), x, 0.5, false);
+        assert_hit(&cone, Vector::new(0.0, -5.0, 0.0), Vector::new(0.0, 1.0, 0.0), 5.0, true);
+    }
+
+    #[test]
+    fn paraboloid() {
+        let paraboloid = Paraboloid {
+            radius: 1.0,
+            height: 1.0,
+            capped: true,
+            material: MaterialId::default(),
+        };
+        let x = Vector::new(1.0, 0.0, 0.0);
+        assert_hit(&paraboloid, Vector::new(-5.0, 0.25, 0.0), x, 4.5, true);
+        assert_hit(&paraboloid, Vector::new(0.0, 0.25, 0.0), x, 0.5, false);
+        assert_hit(&paraboloid, Vector::new(0.0, 5.0, 0.0), Vector::new(0.0, -1.0, 0.0), 4.0, true);
+    }
+
+    #[test]
+    fn hyperboloid() {
+        let hyperboloid = Hyperboloid {
+            waist_radius: 1.0,
+            c: 1.0,
+            y_min: -2.0,
+            y_max: 2.0,
+            capped: false,
+            material: MaterialId::default(),
+        };
+        let x = Vector::new(1.0, 0.0, 0.0);
+        let sqrt_2 = (2.0 as Float).sqrt();
+        assert_hit(&hyperboloid, Vector::new(-5.0, 1.0, 0.0), x, 5.0 - sqrt_2, true);
+        assert_hit(&hyperboloid, Vector::new(0.0, 1.0, 0.0), x, sqrt_2, false);
+        // Tangent to the waist.
+        assert_tangent(&hyperboloid, Vector::new(-5.0, 0.0, 1.0), x, 5.0);
+    }
+}
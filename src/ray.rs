@@ -0,0 +1,6 @@
+use crate::vector::Vector;
+
+pub struct Ray {
+    pub origin: Vector,
+    pub direction: Vector,
+}
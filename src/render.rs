@@ -0,0 +1,354 @@
+use image::{ImageBuffer, Rgb};
+use rand::rngs::SmallRng;
+use rand::{Rng, RngCore, SeedableRng};
+
+use crate::camera::Camera;
+use crate::hittable::HitRecord;
+use crate::material::Material;
+use crate::math::Float;
+use crate::ray::Ray;
+use crate::scene::Scene;
+use crate::vector::Vector;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum RenderMode {
+    /// Direct light plus ambient light and perfect reflection and refraction. Fast, but diffuse
+    /// surfaces receive no light from other surfaces.
+    Whitted,
+    /// Monte Carlo path tracing for global illumination, soft shadows and color bleeding.
+    PathTraced,
+}
+
+pub struct RenderSettings {
+    pub width: u32,
+    pub height: u32,
+    pub samples_per_pixel: u32,
+    /// Bounces followed after the camera ray's hit: reflection and refraction rays in Whitted
+    /// mode, any scattering when path tracing.
+    pub max_depth: u32,
+    pub mode: RenderMode,
+}
+
+/// Distance secondary rays start off the surface, so they do not hit it again.
+const RAY_OFFSET: Float = 1e-4;
+/// Bounces after which paths may be ended by Russian roulette.
+const ROULETTE_DEPTH: u32 = 3;
+
+/// Light reaching `hit` from one sample of each scene light, skipping samples a shadow ray
+/// finds blocked.
+fn direct_light(scene: &Scene, hit: &HitRecord, material: &dyn Material, outgoing: &Vector, rng: &mut dyn RngCore) -> Vector {
+    let mut color = Vector::zero();
+    for light in scene.lights() {
+        let Some(sample) = light.sample(&hit.point, rng) else {
+            continue;
+        };
+        let reflected = material.evaluate(hit, outgoing, &sample.direction);
+        if reflected.is_near_zero() {
+            continue;
+        }
+        let side = if sample.direction.dot(&hit.normal) >= 0.0 { hit.normal } else { -hit.normal };
+        let shadow_ray = Ray {
+            origin: hit.point + side * RAY_OFFSET,
+            direction: sample.direction,
+        };
+        if !scene.occluded(&shadow_ray, 0.0, sample.distance - 2.0 * RAY_OFFSET) {
+            color += reflected * sample.irradiance;
+        }
+    }
+    color
+}
+
+/// Whitted-style radiance along `ray`: emission and direct light at the closest hit, plus the
+/// mirror and refracted rays of specular materials traced until `depth` runs out.
+fn trace_whitted(scene: &Scene, ray: &Ray, depth: u32, rng: &mut dyn RngCore) -> Vector {
+    let Some(hit) = scene.intersect(ray, 0.0, Float::INFINITY) else {
+        return Vector::zero();
+    };
+    let material = scene.material(hit.material);
+    let outgoing = -ray.direction.normalize();
+    let mut color = material.emitted(ray, &hit) + material.ambient(&hit) * scene.ambient_light() + direct_light(scene, &hit, material, &outgoing, rng);
+    if depth == 0 {
+        return color;
+    }
+
+    let specular = material.specular(ray, &hit);
+    if !specular.reflectance.is_near_zero() {
+        let reflected = Ray {
+            origin: hit.point + hit.normal * RAY_OFFSET,
+            direction: (-outgoing).reflect(&hit.normal),
+        };
+        color += specular.reflectance * trace_whitted(scene, &reflected, depth - 1, rng);
+    }
+    if let Some(direction) = specular.refracted.filter(|_| !specular.transmittance.is_near_zero()) {
+        let refracted = Ray {
+            origin: hit.point - hit.normal * RAY_OFFSET,
+            direction,
+        };
+        color += specular.transmittance * trace_whitted(scene, &refracted, depth - 1, rng);
+    }
+    color
+}
+
+/// Path-traced radiance along `ray`. Each hit samples the scene lights directly and then
+/// continues the path in a direction drawn from its material. After a diffuse bounce, emission
+/// is skipped on surfaces an `AreaLight` samples, since light sampling already counted it;
+/// other emitting surfaces light the scene through the path alone.
+fn trace_path(scene: &Scene, ray: &Ray, max_depth: u32, rng: &mut dyn RngCore) -> Vector {
+    let mut radiance = Vector::zero();
+    let mut throughput = Vector::splat(1.0);
+    let mut ray = Ray {
+        origin: ray.origin,
+        direction: ray.direction,
+    };
+    let mut count_emission = true;
+    for depth in 0..=max_depth {
+        let Some(hit) = scene.intersect(&ray, 0.0, Float::INFINITY) else {
+            break;
+        };
+        let material = scene.material(hit.material);
+        let outgoing = -ray.direction.normalize();
+        let emitted = material.emitted(&ray, &hit);
+        if !emitted.is_near_zero() && (count_emission || !scene.lights().iter().any(|light| light.is_hit(&ray, hit.t))) {
+            radiance += throughput * emitted;
+        }
+        radiance += throughput * direct_light(scene, &hit, material, &outgoing, rng);
+        if depth == max_depth {
+            break;
+        }
+
+        let Some(scatter) = material.scatter(&ray, &hit, rng) else {
+            break;
+        };
+        throughput *= scatter.weight;
+        if depth >= ROULETTE_DEPTH {
+            let survival = throughput.max_component().min(0.95);
+            if survival <= 0.0 || rng.gen::<Float>() >= survival {
+                break;
+            }
+            throughput /= survival;
+        }
+        let side = if scatter.direction.dot(&hit.normal) >= 0.0 { hit.normal } else { -hit.normal };
+        ray = Ray {
+            origin: hit.point + side * RAY_OFFSET,
+            direction: scatter.direction,
+        };
+        count_emission = scatter.specular;
+    }
+    radiance
+}
+
+pub fn render_scene(scene: &Scene, camera: &Camera, settings: &RenderSettings) -> ImageBuffer<Rgb<u8>, Vec<u8>> {
+    let mut image: ImageBuffer<Rgb<u8>, Vec<u8>> = ImageBuffer::new(settings.width, settings.height);
+    let mut rng = SmallRng::seed_from_u64(0);
+    let samples = settings.samples_per_pixel.max(1);
+
+    for (x, y, pixel) in image.enumerate_pixels_mut() {
+        let mut color = Vector::zero();
+        for _ in 0..samples {
+            let (jitter_x, jitter_y) = if samples == 1 { (0.5, 0.5) } else { (rng.gen(), rng.gen()) };
+            let s = (x as Float + jitter_x) / settings.width as Float;
+            let t = 1.0 - (y as Float + jitter_y) / settings.height as Float;
+            let Some(ray) = camera.get_ray(s, t, &mut rng) else {
+                continue;
+            };
+            color += match settings.mode {
+                RenderMode::Whitted => trace_whitted(scene, &ray, settings.max_depth, &mut rng),
+                RenderMode::PathTraced => trace_path(scene, &ray, settings.max_depth, &mut rng),
+            };
+        }
+        let color = color / samples as Float * 255.0;
+        *pixel = Rgb([color.x as u8, color.y as u8, color.z as u8]);
+    }
+    //image::imageops::blur(&mut final_img, 255.0);
+
+    image
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::hittable::Hittable;
+    use crate::light::{AreaLight, PointLight};
+    use crate::material::{Dielectric, Emissive, Lambertian, Mirror};
+    use crate::math::consts::PI;
+    use crate::quad::Quad;
+    use crate::sphere::Sphere;
+
+    /// A grey floor under a panel that glows downwards; `sampled` also registers the panel as an
+    /// `AreaLight`.
+    fn lit_floor(sampled: bool) -> Scene {
+        let mut scene = Scene::new();
+        let floor = scene.add_material(Lambertian { albedo: Vector::splat(0.5) });
+        let glow = scene.add_material(Emissive { radiance: Vector::splat(1.0) });
+        scene.add(Quad::new(Vector::new(-5.0, 0.0, 5.0), Vector::new(10.0, 0.0, 0.0), Vector::new(0.0, 0.0, -10.0), floor).unwrap());
+        let panel: Arc<dyn Hittable> =
+            Arc::new(Quad::new(Vector::new(-1.0, 2.0, -1.0), Vector::new(2.0, 0.0, 0.0), Vector::new(0.0, 0.0, 2.0), glow).unwrap());
+        scene.add(panel.clone());
+        if sampled {
+            scene.add_light(AreaLight::new(panel, Vector::splat(1.0)));
+        }
+        scene
+    }
+
+    fn mean_radiance(scene: &Scene, samples: usize) -> Vector {
+        let mut rng = SmallRng::seed_from_u64(4);
+        let ray = Ray {
+            origin: Vector::new(0.0, 1.0, 3.0),
+            direction: Vector::new(0.0, -1.0, -3.0),
+        };
+        (0..samples).fold(Vector::zero(), |sum, _| sum + trace_path(scene, &ray, 4, &mut rng)) / samples as Float
+    }
+
+    #[test]
+    fn emitters_without_a_light_still_light_diffuse_surfaces() {
+        let unsampled = mean_radiance(&lit_floor(false), 50_000);
+        let sampled = mean_radiance(&lit_floor(true), 50_000);
+        assert!(unsampled.x > 0.01, "{unsampled:?}");
+        // Both estimate the same light, and the sampled panel must not be counted twice.
+        assert!((unsampled.x - sampled.x).abs() < 0.05 * sampled.x, "{unsampled:?} != {sampled:?}");
+    }
+
+    #[test]
+    fn renders_in_both_modes() {
+        let mut scene = Scene::new();
+        let red = scene.add_material(Lambertian { albedo: Vector::new(0.8, 0.1, 0.1) });
+        scene.add(Sphere {
+            center: Vector::new(0.0, 0.0, -3.0),
+            radius: 1.0,
+            material: red,
+        });
+        scene.add_light(PointLight {
+            position: Vector::new(2.0, 2.0, 0.0),
+            intensity: Vector::splat(10.0),
+        });
+        let camera = Camera::new(Vector::zero(), Vector::new(0.0, 0.0, -1.0), Vector::new(0.0, 1.0, 0.0), 60.0, 1.0);
+        for mode in [RenderMode::Whitted, RenderMode::PathTraced] {
+            let settings = RenderSettings {
+                width: 16,
+                height: 16,
+                samples_per_pixel: 4,
+                max_depth: 3,
+                mode,
+            };
+            let image = render_scene(&scene, &camera, &settings);
+            assert_eq!(image.dimensions(), (16, 16));
+            // The lit sphere fills the middle and the empty background stays black.
+            let center = image.get_pixel(8, 8);
+            assert!(center[0] > 10 && center[0] > center[1], "{mode:?}: {center:?}");
+            assert_eq!(image.get_pixel(0, 0).0, [0, 0, 0], "{mode:?}");
+        }
+    }
+
+    /// `material` on the z = 0 plane, facing +z between two large panels that glow towards it:
+    /// `above` from z = 5 and `below` from z = -5.
+    fn between_panels(material: impl Material + 'static, above: Vector, below: Vector) -> Scene {
+        let mut scene = Scene::new();
+        let surface = scene.add_material(material);
+        let above = scene.add_material(Emissive { radiance: above });
+        let below = scene.add_material(Emissive { radiance: below });
+        let (x, y) = (Vector::new(2000.0, 0.0, 0.0), Vector::new(0.0, 2000.0, 0.0));
+        let corner = |z: Float| Vector::new(-1000.0, -1000.0, z);
+        scene.add(Quad::new(corner(0.0), x, y, surface).unwrap());
+        scene.add(Quad::new(corner(5.0), y, x, above).unwrap());
+        scene.add(Quad::new(corner(-5.0), x, y, below).unwrap());
+        scene
+    }
+
+    /// Whitted radiance along a ray through the origin, `degrees` from the z axis in the xz
+    /// plane, arriving from above or from below the plane.
+    fn whitted_at(scene: &Scene, degrees: Float, from_above: bool, depth: u32) -> Vector {
+        let angle = degrees.to_radians();
+        let z = if from_above { angle.cos() } else { -angle.cos() };
+        let ray = Ray {
+            origin: Vector::new(-angle.sin(), 0.0, z),
+            direction: Vector::new(angle.sin(), 0.0, -z),
+        };
+        trace_whitted(scene, &ray, depth, &mut SmallRng::seed_from_u64(5))
+    }
+
+    #[test]
+    fn whitted_mirrors_show_what_they_face() {
+        let mirror = Mirror { color: Vector::splat(0.8) };
+        let scene = between_panels(mirror, Vector::new(1.0, 0.5, 0.25), Vector::zero());
+        let seen = whitted_at(&scene, 45.0, true, 1);
+        assert!((seen - Vector::new(0.8, 0.4, 0.2)).length() < 1e-6, "{seen:?}");
+
+        // A lit diffuse sphere in front of the mirror appears with its own shading.
+        let mut scene = Scene::new();
+        let mirror = scene.add_material(Mirror { color: Vector::splat(1.0) });
+        let red = scene.add_material(Lambertian { albedo: Vector::new(0.5, 0.0, 0.0) });
+        scene.add(Quad::new(Vector::new(-10.0, -10.0, 0.0), Vector::new(20.0, 0.0, 0.0), Vector::new(0.0, 20.0, 0.0), mirror).unwrap());
+        // The reflected ray runs along (1, 0, 1) through the sphere's center and meets it
+        // square to a light 1.5 away.
+        let reflected = Vector::new(1.0, 0.0, 1.0).normalize();
+        let center = Vector::new(3.0, 0.0, 3.0);
+        scene.add(Sphere {
+            center,
+            radius: 1.0,
+            material: red,
+        });
+        scene.add_light(PointLight {
+            position: center - reflected * 2.5,
+            intensity: Vector::splat(1.0),
+        });
+        let ray = Ray {
+            origin: Vector::new(-2.0, 0.0, 2.0),
+            direction: Vector::new(1.0, 0.0, -1.0),
+        };
+        let seen = trace_whitted(&scene, &ray, 1, &mut SmallRng::seed_from_u64(5));
+        let expected = 0.5 / PI / (1.5 * 1.5);
+        assert!((seen.x - expected).abs() < 1e-6 && seen.y == 0.0, "{seen:?} != {expected}");
+    }
+
+    #[test]
+    fn whitted_dielectrics_split_energy_by_fresnel() {
+        // Reflections see red and refractions green, so each channel is one side of the split.
+        let glass = Dielectric {
+            ior: 1.5,
+            tint: Vector::splat(1.0),
+        };
+        let scene = between_panels(glass, Vector::new(1.0, 0.0, 0.0), Vector::new(0.0, 1.0, 0.0));
+        let mut previous = 0.0;
+        for degrees in [0.0, 30.0, 60.0, 80.0, 89.0] {
+            let seen = whitted_at(&scene, degrees, true, 1);
+            assert!((seen.x + seen.y - 1.0).abs() < 1e-6, "{degrees}: {seen:?}");
+            assert!(seen.x > previous, "{degrees}: {seen:?}");
+            previous = seen.x;
+        }
+        // Reflection takes over towards grazing angles.
+        assert!((whitted_at(&scene, 0.0, true, 1).x - 0.04).abs() < 1e-6);
+        assert!(previous > 0.9);
+
+        // Past the critical angle of about 42 degrees, light inside the glass is all reflected
+        // back down.
+        assert!(whitted_at(&scene, 30.0, false, 1).x > 0.0);
+        assert_eq!(whitted_at(&scene, 60.0, false, 1), Vector::new(0.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn whitted_follows_no_secondary_rays_at_depth_zero() {
+        let mirror = Mirror { color: Vector::splat(1.0) };
+        let scene = between_panels(mirror, Vector::splat(1.0), Vector::zero());
+        assert_eq!(whitted_at(&scene, 45.0, true, 0), Vector::zero());
+        assert!(whitted_at(&scene, 45.0, true, 1).x > 0.99);
+
+        // Each bounce between two mirrors uses up one level of depth.
+        let mut scene = Scene::new();
+        let mirror = scene.add_material(Mirror { color: Vector::splat(0.5) });
+        let glow = scene.add_material(Emissive { radiance: Vector::splat(1.0) });
+        let (x, y) = (Vector::new(20.0, 0.0, 0.0), Vector::new(0.0, 20.0, 0.0));
+        scene.add(Quad::new(Vector::new(-10.0, -10.0, 0.0), x, y, mirror).unwrap());
+        scene.add(Quad::new(Vector::new(-10.0, -10.0, 1.0), y, x, mirror).unwrap());
+        scene.add(Quad::new(Vector::new(8.0, -10.0, -1.0), Vector::new(0.0, 0.0, 3.0), y, glow).unwrap());
+        let ray = Ray {
+            origin: Vector::new(0.0, 0.0, 0.5),
+            direction: Vector::new(1.0, 0.0, -0.5),
+        };
+        let mut rng = SmallRng::seed_from_u64(5);
+        // The ray bounces at x = 1, 3, 5 and 7 before it reaches the glowing wall at x = 8.
+        assert_eq!(trace_whitted(&scene, &ray, 3, &mut rng), Vector::zero());
+        assert!((trace_whitted(&scene, &ray, 4, &mut rng).x - 0.5 * 0.5 * 0.5 * 0.5).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,247 @@
+use std::sync::{Arc, OnceLock};
+
+use crate::aabb::Aabb;
+use crate::bvh::{Bvh, REBUILD_COST_RATIO};
+use crate::hittable::{HitRecord, Hittable};
+use crate::instance::Instance;
+use crate::light::Light;
+use crate::material::{Lambertian, Material, MaterialId};
+use crate::math::Float;
+use crate::ray::Ray;
+use crate::tlas::Tlas;
+use crate::transform::Transform;
+use crate::vector::Vector;
+
+/// The BVH covers objects with finite bounds; unbounded ones such as planes are tested linearly.
+struct SceneBvh {
+    bvh: Bvh,
+    bounded: Vec<usize>,
+    unbounded: Vec<usize>,
+}
+
+/// Objects added directly sit in the scene's own BVH; instances of shared geometry go into a
+/// separate top-level structure that can be moved cheaply. Objects refer to the scene's
+/// materials by `MaterialId`, so replacing a material restyles everything that uses it.
+/// Lights are kept apart from the geometry and only shine through direct lighting.
+pub struct Scene {
+    objects: Vec<Box<dyn Hittable>>,
+    bvh: OnceLock<SceneBvh>,
+    instances: Tlas,
+    materials: Vec<Box<dyn Material>>,
+    lights: Vec<Box<dyn Light>>,
+    ambient_light: Vector,
+}
+
+impl Default for Scene {
+    fn default() -> Scene {
+        Scene::new()
+    }
+}
+
+impl Scene {
+    /// Starts with a grey diffuse material as `MaterialId::default()`, for objects that do not
+    /// specify one.
+    pub fn new() -> Scene {
+        Scene {
+            objects: Vec::new(),
+            bvh: OnceLock::new(),
+            instances: Tlas::new(),
+            materials: vec![Box::new(Lambertian { albedo: Vector::splat(0.8) })],
+            lights: Vec::new(),
+            ambient_light: Vector::zero(),
+        }
+    }
+
+    pub fn add_material(&mut self, material: impl Material + 'static) -> MaterialId {
+        self.add_boxed_material(Box::new(material))
+    }
+
+    pub fn add_boxed_material(&mut self, material: Box<dyn Material>) -> MaterialId {
+        self.materials.push(material);
+        MaterialId(self.materials.len() - 1)
+    }
+
+    /// Replaces the material behind `id` for every object that uses it.
+    pub fn set_material(&mut self, id: MaterialId, material: impl Material + 'static) {
+        self.materials[id.0] = Box::new(material);
+    }
+
+    pub fn material(&self, id: MaterialId) -> &dyn Material {
+        self.materials[id.0].as_ref()
+    }
+
+    /// Returns the light's index within `lights`.
+    pub fn add_light(&mut self, light: impl Light + 'static) -> usize {
+        self.lights.push(Box::new(light));
+        self.lights.len() - 1
+    }
+
+    pub fn set_light(&mut self, index: usize, light: impl Light + 'static) {
+        self.lights[index] = Box::new(light);
+    }
+
+    pub fn lights(&self) -> &[Box<dyn Light>] {
+        &self.lights
+    }
+
+    /// Light arriving equally from everywhere, reflected by materials with an ambient term.
+    pub fn set_ambient_light(&mut self, ambient_light: Vector) {
+        self.ambient_light = ambient_light;
+    }
+
+    pub fn ambient_light(&self) -> Vector {
+        self.ambient_light
+    }
+
+    pub fn add(&mut self, object: impl Hittable + 'static) {
+        self.objects.push(Box::new(object));
+        self.bvh = OnceLock::new();
+    }
+
+    pub fn objects(&self) -> &[Box<dyn Hittable>] {
+        &self.objects
+    }
+
+    /// Places shared geometry, returning the index of the instance within `instances`.
+    pub fn add_instance(&mut self, object: Arc<dyn Hittable>, transform: Transform) -> usize {
+        self.instances.add(Instance::new(object, transform))
+    }
+
+    pub fn instances(&self) -> &Tlas {
+        &self.instances
+    }
+
+    /// Moves instances without touching their shared geometry; see `Tlas::update`.
+    pub fn update_instances(&mut self, edit: impl FnOnce(&mut [Instance])) -> bool {
+        self.instances.update(edit)
+    }
+
+    /// Lets `edit` move or replace objects, then refits the BVH to their new bounds instead of
+    /// building it again. Returns whether the BVH had to be rebuilt because refitting degraded
+    /// it too far or an object became bounded or unbounded.
+    pub fn update(&mut self, edit: impl FnOnce(&mut [Box<dyn Hittable>])) -> bool {
+        edit(&mut self.objects);
+        let Some(scene_bvh) = self.bvh.get_mut() else {
+            return false;
+        };
+        let bounds = scene_bvh.bounded.iter().map(|index| self.objects[*index].bounding_box()).collect::<Vec<Aabb>>();
+        let still_partitioned = bounds.iter().all(Aabb::is_finite)
+            && scene_bvh.unbounded.iter().all(|index| !self.objects[*index].bounding_box().is_finite());
+        if still_partitioned {
+            return scene_bvh.bvh.refit_or_rebuild(&bounds, REBUILD_COST_RATIO);
+        }
+        self.bvh = OnceLock::new();
+        true
+    }
+
+    /// Builds the BVH now rather than on the first intersection.
+    pub fn build_bvh(&self) -> &Bvh {
+        &self.scene_bvh().bvh
+    }
+
+    fn scene_bvh(&self) -> &SceneBvh {
+        self.bvh.get_or_init(|| {
+            let bounds = self.objects.iter().map(|object| object.bounding_box()).collect::<Vec<Aabb>>();
+            let (bounded, unbounded): (Vec<usize>, Vec<usize>) = (0..self.objects.len()).partition(|index| bounds[*index].is_finite());
+            let bvh = Bvh::build(&bounded.iter().map(|index| bounds[*index]).collect::<Vec<Aabb>>());
+            SceneBvh { bvh, bounded, unbounded }
+        })
+    }
+
+    pub fn intersect(&self, ray: &Ray, t_min: Float, t_max: Float) -> Option<HitRecord> {
+        let scene_bvh = self.scene_bvh();
+        let mut closest = None;
+        let mut t_max = t_max;
+        for index in &scene_bvh.unbounded {
+            if let Some(hit) = self.objects[*index].intersect(ray, t_min, t_max) {
+                t_max = hit.t;
+                closest = Some(hit);
+            }
+        }
+        let bounded_hit = scene_bvh.bvh.intersect(ray, t_min, t_max, |primitive, t_max| {
+            self.objects[scene_bvh.bounded[primitive]].intersect(ray, t_min, t_max)
+        });
+        let closest = bounded_hit.or(closest);
+        let t_max = closest.as_ref().map_or(t_max, |hit| hit.t);
+        self.instances.intersect(ray, t_min, t_max).or(closest)
+    }
+
+    pub fn occluded(&self, ray: &Ray, t_min: Float, t_max: Float) -> bool {
+        let scene_bvh = self.scene_bvh();
+        scene_bvh.unbounded.iter().any(|index| self.objects[*index].occluded(ray, t_min, t_max))
+            || scene_bvh.bvh.occluded(ray, t_min, t_max, |primitive| self.objects[scene_bvh.bounded[primitive]].occluded(ray, t_min, t_max))
+            || self.instances.occluded(ray, t_min, t_max)
+    }
+
+    pub fn bounding_box(&self) -> Aabb {
+        let bounds = self.objects.iter().fold(Aabb::empty(), |bounds, object| bounds.union(&object.bounding_box()));
+        bounds.union(&self.instances.bounding_box())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::SmallRng;
+    use rand::{Rng, SeedableRng};
+
+    use super::*;
+    use crate::plane::Plane;
+    use crate::sphere::Sphere;
+
+    fn random_vector(rng: &mut SmallRng, extent: Float) -> Vector {
+        Vector::new(rng.gen_range(-extent..extent), rng.gen_range(-extent..extent), rng.gen_range(-extent..extent))
+    }
+
+    fn sphere(center: Vector, radius: Float) -> Box<dyn Hittable> {
+        Box::new(Sphere {
+            center,
+            radius,
+            material: MaterialId::default(),
+        })
+    }
+
+    /// Checks the scene's BVH against testing every object.
+    fn assert_matches_brute_force(scene: &Scene, rays: &[Ray]) {
+        for ray in rays {
+            let closest = |t_max: Float| {
+                scene.objects().iter().filter_map(|object| object.intersect(ray, 0.0, t_max)).map(|hit| hit.t).min_by(Float::total_cmp)
+            };
+            assert_eq!(scene.intersect(ray, 0.0, Float::INFINITY).map(|hit| hit.t), closest(Float::INFINITY));
+            assert_eq!(scene.occluded(ray, 0.0, 40.0), closest(40.0).is_some());
+        }
+    }
+
+    #[test]
+    fn bvh_matches_brute_force_before_and_after_update() {
+        let mut rng = SmallRng::seed_from_u64(9);
+        let mut scene = Scene::new();
+        // The unbounded plane stays outside the BVH.
+        scene.add(Plane {
+            point: Vector::new(0.0, -60.0, 0.0),
+            normal: Vector::new(0.0, 1.0, 0.0),
+            material: MaterialId::default(),
+        });
+        let mut spheres = (0..500).map(|_| (random_vector(&mut rng, 50.0), rng.gen_range(0.2..3.0))).collect::<Vec<(Vector, Float)>>();
+        for (center, radius) in &spheres {
+            scene.objects.push(sphere(*center, *radius));
+        }
+        let rays = (0..20_000)
+            .map(|_| Ray {
+                origin: random_vector(&mut rng, 80.0),
+                direction: random_vector(&mut rng, 1.0),
+            })
+            .collect::<Vec<Ray>>();
+        assert_matches_brute_force(&scene, &rays);
+
+        for (center, _) in &mut spheres {
+            *center += random_vector(&mut rng, 5.0);
+        }
+        let rebuilt = scene.update(|objects| {
+            for (object, (center, radius)) in objects[1..].iter_mut().zip(&spheres) {
+                *object = sphere(*center, *radius);
+            }
+        });
+        assert!(!rebuilt);
+        assert_matches_brute_force(&scene, &rays);
+    }
+}
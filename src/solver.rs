@@ -0,0 +1,197 @@
+//! Real polynomial root finding. Everything runs in `f64` regardless of `Float` because the
+//! quartic is badly conditioned in single precision.
+
+use std::f64::consts::PI;
+
+/// Real roots of `a t^2 + b t + c`, ascending. Degenerates to the linear case when `a` is zero.
+pub fn solve_quadratic(a: f64, b: f64, c: f64) -> Option<(f64, f64)> {
+    if a == 0.0 {
+        if b == 0.0 {
+            return None;
+        }
+        let t = -c / b;
+        return Some((t, t));
+    }
+    let discriminant = b * b - 4.0 * a * c;
+    if discriminant < 0.0 {
+        return None;
+    }
+    // Avoids cancellation between `-b` and the root of the discriminant.
+    let q = -0.5 * (b + b.signum() * discriminant.sqrt());
+    let (t0, t1) = if q == 0.0 { (0.0, 0.0) } else { (q / a, c / q) };
+    Some(if t0 < t1 { (t0, t1) } else { (t1, t0) })
+}
+
+/// Real roots of the monic cubic `t^3 + a t^2 + b t + c`, ascending. A double root is listed
+/// twice.
+pub fn solve_cubic(a: f64, b: f64, c: f64) -> Vec<f64> {
+    let q = (a * a - 3.0 * b) / 9.0;
+    let r = (2.0 * a * a * a - 9.0 * a * b + 27.0 * c) / 54.0;
+    let shift = a / 3.0;
+
+    // `r^2 == q^3` is a double root, which the trigonometric form handles with `theta` at 0 or
+    // pi, so rounding just above it must not fall through to the single-root branch.
+    let q3 = q * q * q;
+    let mut roots = if q > 0.0 && r * r - q3 <= 1e-12 * q3 {
+        let theta = (r / q3.sqrt()).clamp(-1.0, 1.0).acos();
+        let scale = -2.0 * q.sqrt();
+        vec![
+            scale * (theta / 3.0).cos() - shift,
+            scale * ((theta + 2.0 * PI) / 3.0).cos() - shift,
+            scale * ((theta - 2.0 * PI) / 3.0).cos() - shift,
+        ]
+    } else {
+        let big_a = -r.signum() * (r.abs() + (r * r - q3).sqrt()).cbrt();
+        let big_b = if big_a == 0.0 { 0.0 } else { q / big_a };
+        vec![big_a + big_b - shift]
+    };
+    roots.sort_by(f64::total_cmp);
+    roots
+}
+
+/// Real roots of `c4 t^4 + c3 t^3 + c2 t^2 + c1 t + c0`, ascending.
+///
+/// Uses Ferrari's method on the depressed quartic and polishes each root with Newton steps on
+/// the original polynomial, which recovers most of the precision lost in the reduction.
+pub fn solve_quartic(c4: f64, c3: f64, c2: f64, c1: f64, c0: f64) -> Vec<f64> {
+    if c4 == 0.0 {
+        return if c3 == 0.0 {
+            solve_quadratic(c2, c1, c0).map_or(Vec::new(), |(t0, t1)| vec![t0, t1])
+        } else {
+            solve_cubic(c2 / c3, c1 / c3, c0 / c3)
+        };
+    }
+    let (a, b, c, d) = (c3 / c4, c2 / c4, c1 / c4, c0 / c4);
+
+    // Substituting t = y - a/4 gives y^4 + p y^2 + q y + r.
+    let a2 = a * a;
+    let p = b - 3.0 * a2 / 8.0;
+    let q = c - a * b / 2.0 + a2 * a / 8.0;
+    let r = d - a * c / 4.0 + a2 * b / 16.0 - 3.0 * a2 * a2 / 256.0;
+
+    let mut ys = Vec::with_capacity(4);
+    if q.abs() < 1e-12 {
+        // Biquadratic: solve for y^2.
+        if let Some((z0, z1)) = solve_quadratic(1.0, p, r) {
+            for z in [z0, z1] {
+                if z >= 0.0 {
+                    ys.push(z.sqrt());
+                    ys.push(-z.sqrt());
+                }
+            }
+        }
+    } else {
+        // Any positive root of the resolvent cubic splits the quartic into two quadratics.
+        let z = solve_cubic(2.0 * p, p * p - 4.0 * r, -q * q).into_iter().fold(0.0, f64::max);
+        if z <= 0.0 {
+            return Vec::new();
+        }
+        let s = z.sqrt();
+        let half = (p + z) / 2.0;
+        let offset = q / (2.0 * s);
+        for (linear, constant) in [(s, half - offset), (-s, half + offset)] {
+            if let Some((y0, y1)) = solve_quadratic(1.0, linear, constant) {
+                ys.push(y0);
+                ys.push(y1);
+            }
+        }
+    }
+
+    let mut roots = ys
+        .into_iter()
+        .map(|y| polish_root(y - a / 4.0, [d, c, b, a, 1.0]))
+        .collect::<Vec<f64>>();
+    roots.sort_by(f64::total_cmp);
+    roots
+}
+
+/// A few Newton iterations on the polynomial with ascending `coefficients`.
+fn polish_root(mut t: f64, coefficients: [f64; 5]) -> f64 {
+    for _ in 0..3 {
+        let mut value = 0.0;
+        let mut derivative = 0.0;
+        for coefficient in coefficients.iter().rev() {
+            derivative = derivative * t + value;
+            value = value * t + coefficient;
+        }
+        if derivative == 0.0 {
+            break;
+        }
+        let step = value / derivative;
+        t -= step;
+        if step.abs() <= 1e-14 * t.abs().max(1.0) {
+            break;
+        }
+    }
+    t
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_roots(roots: &[f64], expected: &[f64]) {
+        assert_eq!(roots.len(), expected.len(), "{roots:?} != {expected:?}");
+        for (root, expected) in roots.iter().zip(expected) {
+            assert!((root - expected).abs() < 1e-6, "{roots:?} != {expected:?}");
+        }
+    }
+
+    #[test]
+    fn quadratic_roots() {
+        assert_eq!(solve_quadratic(1.0, -3.0, 2.0), Some((1.0, 2.0)));
+        assert_eq!(solve_quadratic(1.0, -2.0, 1.0), Some((1.0, 1.0)));
+        assert_eq!(solve_quadratic(1.0, 0.0, 1.0), None);
+        assert_eq!(solve_quadratic(0.0, 2.0, -4.0), Some((2.0, 2.0)));
+    }
+
+    #[test]
+    fn cubic_roots() {
+        // (t - 1)(t - 2)(t - 3)
+        assert_roots(&solve_cubic(-6.0, 11.0, -6.0), &[1.0, 2.0, 3.0]);
+        // (t - 2)(t^2 + 1)
+        assert_roots(&solve_cubic(-2.0, 1.0, -2.0), &[2.0]);
+    }
+
+    #[test]
+    fn cubic_double_and_triple_roots() {
+        // (t - 1)^2 (t + 2), where r^2 == q^3 exactly.
+        assert_roots(&solve_cubic(0.0, -3.0, 2.0), &[-2.0, 1.0, 1.0]);
+        // (t - 1)^2 (t - 2)
+        assert_roots(&solve_cubic(-4.0, 5.0, -2.0), &[1.0, 1.0, 2.0]);
+        // (t - 3)^3
+        assert_roots(&solve_cubic(-9.0, 27.0, -27.0), &[3.0]);
+    }
+
+    #[test]
+    fn quartic_distinct_roots() {
+        // (t - 1)(t - 2)(t - 3)(t - 4)
+        assert_roots(&solve_quartic(1.0, -10.0, 35.0, -50.0, 24.0), &[1.0, 2.0, 3.0, 4.0]);
+        // Same roots with a leading coefficient to divide out.
+        assert_roots(&solve_quartic(2.0, -20.0, 70.0, -100.0, 48.0), &[1.0, 2.0, 3.0, 4.0]);
+    }
+
+    #[test]
+    fn quartic_double_roots() {
+        // (t - 1)^2 (t - 2)^2
+        let roots = solve_quartic(1.0, -6.0, 13.0, -12.0, 4.0);
+        assert!(!roots.is_empty());
+        for root in &roots {
+            assert!((root - 1.0).abs() < 1e-6 || (root - 2.0).abs() < 1e-6, "{roots:?}");
+        }
+        assert!(roots.iter().any(|root| (root - 1.0).abs() < 1e-6));
+        assert!(roots.iter().any(|root| (root - 2.0).abs() < 1e-6));
+    }
+
+    #[test]
+    fn quartic_without_real_roots() {
+        // (t^2 + 1)(t^2 + 4)
+        assert!(solve_quartic(1.0, 0.0, 5.0, 0.0, 4.0).is_empty());
+    }
+
+    #[test]
+    fn quartic_falls_back_to_lower_degrees() {
+        assert_roots(&solve_quartic(0.0, 1.0, -6.0, 11.0, -6.0), &[1.0, 2.0, 3.0]);
+        assert_roots(&solve_quartic(0.0, 0.0, 1.0, -3.0, 2.0), &[1.0, 2.0]);
+    }
+}
@@ -0,0 +1,138 @@
+use crate::aabb::Aabb;
+use crate::hittable::{HitRecord, Hittable, SurfaceSample};
+use crate::material::MaterialId;
+use crate::math::consts::PI;
+use crate::math::Float;
+use crate::ray::Ray;
+use crate::vector::Vector;
+
+pub struct Sphere {
+    pub center: Vector,
+    pub radius: Float,
+    pub material: MaterialId,
+}
+
+impl Hittable for Sphere {
+    fn intersect(&self, ray: &Ray, t_min: Float, t_max: Float) -> Option<HitRecord> {
+        let oc = ray.origin - self.center;
+        let a = ray.direction.length_squared();
+        let half_b = oc.dot(&ray.direction);
+        let c = oc.length_squared() - self.radius * self.radius;
+        let discriminant = half_b * half_b - a * c;
+        if discriminant < 0.0 {
+            return None;
+        }
+        let sqrt_d = discriminant.sqrt();
+
+        // Prefer the near root; fall back to the far one when the ray starts inside the sphere.
+        let mut t = (-half_b - sqrt_d) / a;
+        if t <= t_min || t >= t_max {
+            t = (-half_b + sqrt_d) / a;
+            if t <= t_min || t >= t_max {
+                return None;
+            }
+        }
+
+        let point = ray.origin + ray.direction * t;
+        let outward_normal = (point - self.center) / self.radius;
+        let (normal, front_face) = HitRecord::face_normal(ray, outward_normal);
+
+        let theta = (-outward_normal.y).clamp(-1.0, 1.0).acos();
+        let phi = (-outward_normal.z).atan2(outward_normal.x) + PI;
+
+        Some(HitRecord {
+            t,
+            point,
+            normal,
+            front_face,
+            u: phi / (2.0 * PI),
+            v: theta / PI,
+            material: self.material,
+            vertex_color: None,
+            tangent: None,
+        })
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        let extent = Vector::new(self.radius, self.radius, self.radius);
+        Aabb::new(self.center - extent, self.center + extent)
+    }
+
+    fn sample_surface(&self, u: Float, v: Float) -> SurfaceSample {
+        let z = 1.0 - 2.0 * u;
+        let r = (1.0 - z * z).max(0.0).sqrt();
+        let phi = 2.0 * PI * v;
+        let normal = Vector::new(r * phi.cos(), r * phi.sin(), z);
+        SurfaceSample {
+            point: self.center + normal * self.radius,
+            normal,
+            pdf: 1.0 / (4.0 * PI * self.radius * self.radius),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sphere() -> Sphere {
+        Sphere {
+            center: Vector::new(0.0, 0.0, -5.0),
+            radius: 2.0,
+            material: MaterialId::default(),
+        }
+    }
+
+    fn hit(origin: Vector, direction: Vector, t_min: Float, t_max: Float) -> Option<HitRecord> {
+        sphere().intersect(&Ray { origin, direction }, t_min, t_max)
+    }
+
+    #[test]
+    fn hits_the_near_side_from_outside() {
+        let hit = hit(Vector::zero(), Vector::new(0.0, 0.0, -1.0), 0.0, Float::INFINITY).unwrap();
+        assert_eq!((hit.t, hit.point, hit.normal, hit.front_face), (3.0, Vector::new(0.0, 0.0, -3.0), Vector::new(0.0, 0.0, 1.0), true));
+    }
+
+    #[test]
+    fn hits_the_far_side_from_inside() {
+        let hit = hit(Vector::new(0.0, 0.0, -5.0), Vector::new(1.0, 0.0, 0.0), 0.0, Float::INFINITY).unwrap();
+        assert_eq!((hit.t, hit.point, hit.front_face), (2.0, Vector::new(2.0, 0.0, -5.0), false));
+        // The outward normal is +x; it is flipped to face the ray.
+        assert_eq!(hit.normal, Vector::new(-1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn measures_t_in_multiples_of_the_direction() {
+        let hit = hit(Vector::zero(), Vector::new(0.0, 0.0, -4.0), 0.0, Float::INFINITY).unwrap();
+        assert_eq!((hit.t, hit.point), (0.75, Vector::new(0.0, 0.0, -3.0)));
+    }
+
+    #[test]
+    fn rejects_roots_outside_the_t_range() {
+        let direction = Vector::new(0.0, 0.0, -1.0);
+        // Both roots, at 3 and 7, lie beyond `t_max` or before `t_min`.
+        assert!(hit(Vector::zero(), direction, 0.0, 2.5).is_none());
+        assert!(hit(Vector::zero(), direction, 7.5, Float::INFINITY).is_none());
+        // Only the far root is inside the range.
+        assert_eq!(hit(Vector::zero(), direction, 4.0, Float::INFINITY).unwrap().t, 7.0);
+        assert!(hit(Vector::new(0.0, 3.0, 0.0), direction, 0.0, Float::INFINITY).is_none());
+    }
+
+    #[test]
+    fn uv_runs_pole_to_pole_and_around_from_the_seam() {
+        let down = Vector::new(0.0, -1.0, 0.0);
+        let top = hit(Vector::new(0.0, 5.0, -5.0), down, 0.0, Float::INFINITY).unwrap();
+        assert_eq!(top.v, 1.0);
+        let bottom = hit(Vector::new(0.0, -5.0, -5.0), -down, 0.0, Float::INFINITY).unwrap();
+        assert_eq!(bottom.v, 0.0);
+
+        let towards = |x: Float, z: Float| hit(Vector::new(10.0 * x, 0.0, -5.0 + 10.0 * z), Vector::new(-x, 0.0, -z), 0.0, Float::INFINITY).unwrap();
+        let front = towards(0.0, 1.0);
+        assert!((front.u - 0.25).abs() < 1e-6 && (front.v - 0.5).abs() < 1e-6, "{} {}", front.u, front.v);
+        assert!((towards(1.0, 0.0).u - 0.5).abs() < 1e-6);
+        assert!((towards(0.0, -1.0).u - 0.75).abs() < 1e-6);
+        // The seam is at -x: just on either side u is close to 0 and 1.
+        assert!(towards(-1.0, 0.01).u < 0.01);
+        assert!(towards(-1.0, -0.01).u > 0.99);
+    }
+}
@@ -0,0 +1,143 @@
+use image::{ImageBuffer, Rgb};
+
+use crate::camera::Camera;
+use crate::math::Float;
+use crate::render::{render_scene, RenderSettings};
+use crate::scene::Scene;
+
+#[derive(Copy, Clone, Debug)]
+pub enum StereoLayout {
+    /// Left eye in the left half, right eye in the right half.
+    SideBySide,
+    /// Left eye on top, right eye below.
+    OverUnder,
+    /// Red channel from the left eye, green and blue from the right eye.
+    Anaglyph,
+}
+
+pub struct StereoRig {
+    pub camera: Camera,
+    pub interaxial: Float,
+    pub convergence: Float,
+}
+
+impl StereoRig {
+    pub fn left_eye(&self) -> Camera {
+        self.camera.clone().with_stereo_offset(-self.interaxial / 2.0, self.convergence)
+    }
+
+    pub fn right_eye(&self) -> Camera {
+        self.camera.clone().with_stereo_offset(self.interaxial / 2.0, self.convergence)
+    }
+}
+
+/// Renders both eyes at `settings` resolution and packs them into a single image.
+pub fn render_stereo(scene: &Scene, rig: &StereoRig, settings: &RenderSettings, layout: StereoLayout) -> ImageBuffer<Rgb<u8>, Vec<u8>> {
+    let left = render_scene(scene, &rig.left_eye(), settings);
+    let right = render_scene(scene, &rig.right_eye(), settings);
+    pack_stereo(&left, &right, layout)
+}
+
+/// Packs two equally sized eye images according to `layout`.
+pub fn pack_stereo(left: &ImageBuffer<Rgb<u8>, Vec<u8>>, right: &ImageBuffer<Rgb<u8>, Vec<u8>>, layout: StereoLayout) -> ImageBuffer<Rgb<u8>, Vec<u8>> {
+    let (width, height) = left.dimensions();
+
+    match layout {
+        StereoLayout::SideBySide => ImageBuffer::from_fn(width * 2, height, |x, y| {
+            if x < width {
+                *left.get_pixel(x, y)
+            } else {
+                *right.get_pixel(x - width, y)
+            }
+        }),
+        StereoLayout::OverUnder => ImageBuffer::from_fn(width, height * 2, |x, y| {
+            if y < height {
+                *left.get_pixel(x, y)
+            } else {
+                *right.get_pixel(x, y - height)
+            }
+        }),
+        StereoLayout::Anaglyph => ImageBuffer::from_fn(width, height, |x, y| {
+            let l = left.get_pixel(x, y).0;
+            let r = right.get_pixel(x, y).0;
+            Rgb([l[0], r[1], r[2]])
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::SmallRng;
+    use rand::SeedableRng;
+
+    use super::*;
+    use crate::camera::Projection;
+    use crate::vector::Vector;
+
+    fn assert_close(actual: Vector, expected: Vector) {
+        assert!((actual - expected).length() < 1e-5, "{actual:?} != {expected:?}");
+    }
+
+    /// Rig at the origin looking down -z, so the camera's right vector is +x.
+    fn rig(projection: Projection, convergence: Float) -> StereoRig {
+        StereoRig {
+            camera: Camera::with_projection(Vector::zero(), Vector::new(0.0, 0.0, -1.0), Vector::new(0.0, 1.0, 0.0), projection),
+            interaxial: 0.2,
+            convergence,
+        }
+    }
+
+    #[test]
+    fn eyes_sit_half_the_interaxial_apart_and_converge() {
+        let rig = rig(Projection::Perspective { vfov_degrees: 60.0, aspect_ratio: 1.0 }, 5.0);
+        let mut rng = SmallRng::seed_from_u64(1);
+        let left = rig.left_eye().get_ray(0.5, 0.5, &mut rng).unwrap();
+        let right = rig.right_eye().get_ray(0.5, 0.5, &mut rng).unwrap();
+        assert_close(left.origin, Vector::new(-0.1, 0.0, 0.0));
+        assert_close(right.origin, Vector::new(0.1, 0.0, 0.0));
+        // Both centre rays cross the axis at the convergence distance.
+        for ray in [left, right] {
+            let t = -ray.origin.x / ray.direction.x;
+            assert_close(ray.origin + ray.direction * t, Vector::new(0.0, 0.0, -5.0));
+        }
+    }
+
+    #[test]
+    fn panoramic_eyes_are_tangent_to_the_viewing_circle() {
+        let rig = rig(Projection::Equirectangular, Float::INFINITY);
+        let mut rng = SmallRng::seed_from_u64(2);
+        for (eye, sign) in [(rig.left_eye(), -1.0), (rig.right_eye(), 1.0)] {
+            for s in [0.0, 0.1, 0.25, 0.5, 0.6, 0.9] {
+                for t in [0.3, 0.5, 0.8] {
+                    let ray = eye.get_ray(s, t, &mut rng).unwrap();
+                    let horizontal = Vector::new(ray.direction.x, 0.0, ray.direction.z).normalize();
+                    assert!((ray.origin.length() - 0.1).abs() < 1e-6 && ray.origin.y == 0.0, "{:?}", ray.origin);
+                    assert!(ray.origin.dot(&horizontal).abs() < 1e-6, "{s} {t}: {:?}", ray.origin);
+                    // Each eye stays on its own side of the view direction.
+                    let right = Vector::new(0.0, 1.0, 0.0).cross(&-horizontal);
+                    assert!(ray.origin.dot(&right) * sign > 0.0, "{s} {t}: {:?}", ray.origin);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn packs_each_eye_into_its_own_pixels_and_channels() {
+        let left = ImageBuffer::from_pixel(3, 2, Rgb([10, 20, 30]));
+        let right = ImageBuffer::from_pixel(3, 2, Rgb([40, 50, 60]));
+
+        let side_by_side = pack_stereo(&left, &right, StereoLayout::SideBySide);
+        assert_eq!(side_by_side.dimensions(), (6, 2));
+        assert_eq!(side_by_side.get_pixel(2, 1).0, [10, 20, 30]);
+        assert_eq!(side_by_side.get_pixel(3, 0).0, [40, 50, 60]);
+
+        let over_under = pack_stereo(&left, &right, StereoLayout::OverUnder);
+        assert_eq!(over_under.dimensions(), (3, 4));
+        assert_eq!(over_under.get_pixel(2, 1).0, [10, 20, 30]);
+        assert_eq!(over_under.get_pixel(0, 2).0, [40, 50, 60]);
+
+        let anaglyph = pack_stereo(&left, &right, StereoLayout::Anaglyph);
+        assert_eq!(anaglyph.dimensions(), (3, 2));
+        assert_eq!(anaglyph.get_pixel(1, 1).0, [10, 50, 60]);
+    }
+}
@@ -0,0 +1,307 @@
+//! STL import, ASCII and binary.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::material::MaterialId;
+use crate::math::Float;
+use crate::mesh::TriangleMesh;
+use crate::texture::srgb_to_linear;
+use crate::vector::Vector;
+
+#[derive(Debug)]
+pub enum StlError {
+    Io { path: PathBuf, source: io::Error },
+    Parse { path: PathBuf, message: String },
+}
+
+impl fmt::Display for StlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StlError::Io { path, source } => write!(f, "{}: {}", path.display(), source),
+            StlError::Parse { path, message } => write!(f, "{}: {}", path.display(), message),
+        }
+    }
+}
+
+impl std::error::Error for StlError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            StlError::Io { source, .. } => Some(source),
+            StlError::Parse { .. } => None,
+        }
+    }
+}
+
+struct Facet {
+    normal: Vector,
+    vertices: [Vector; 3],
+    color: Option<Vector>,
+}
+
+/// Loads an STL file and welds coincident vertices into a shared, smooth-shaded mesh. Edges
+/// whose faces meet at more than `crease_degrees` stay sharp; pass 180 to smooth everything.
+///
+/// Binary files using the VisCAM/SolidView convention for per-facet colors (bit 15 of the
+/// attribute word set) get those colors as vertex colors, decoded from sRGB like integer PLY
+/// colors.
+pub fn load_stl(path: impl AsRef<Path>, crease_degrees: Float) -> Result<TriangleMesh, StlError> {
+    let path = path.as_ref();
+    let bytes = fs::read(path).map_err(|source| StlError::Io {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    parse_stl(&bytes, crease_degrees).map_err(|message| StlError::Parse {
+        path: path.to_path_buf(),
+        message,
+    })
+}
+
+fn parse_stl(bytes: &[u8], crease_degrees: Float) -> Result<TriangleMesh, String> {
+    let facets = if is_binary(bytes) { parse_binary(bytes)? } else { parse_ascii(bytes)? };
+    Ok(weld(&facets, crease_degrees))
+}
+
+/// ASCII files start with `solid`, but so do many binary headers, so the size decides.
+fn is_binary(bytes: &[u8]) -> bool {
+    if bytes.len() < 84 {
+        return !bytes.starts_with(b"solid");
+    }
+    let count = u32::from_le_bytes([bytes[80], bytes[81], bytes[82], bytes[83]]) as usize;
+    bytes.len() == 84 + count * 50 || !bytes.starts_with(b"solid")
+}
+
+fn parse_binary(bytes: &[u8]) -> Result<Vec<Facet>, String> {
+    if bytes.len() < 84 {
+        return Err("binary file shorter than its 84-byte header".to_string());
+    }
+    let count = u32::from_le_bytes([bytes[80], bytes[81], bytes[82], bytes[83]]) as usize;
+    if bytes.len() < 84 + count * 50 {
+        return Err(format!("header declares {count} facets but the file holds {}", (bytes.len() - 84) / 50));
+    }
+    let float = |offset: usize| f32::from_le_bytes([bytes[offset], bytes[offset + 1], bytes[offset + 2], bytes[offset + 3]]) as Float;
+    let vector = |offset: usize| Vector::new(float(offset), float(offset + 4), float(offset + 8));
+    Ok((0..count)
+        .map(|index| {
+            let offset = 84 + index * 50;
+            let attribute = u16::from_le_bytes([bytes[offset + 48], bytes[offset + 49]]);
+            let channel = |shift: u16| srgb_to_linear(((attribute >> shift) & 0x1f) as Float / 31.0);
+            Facet {
+                normal: vector(offset),
+                vertices: [vector(offset + 12), vector(offset + 24), vector(offset + 36)],
+                color: (attribute & 0x8000 != 0).then(|| Vector::new(channel(10), channel(5), channel(0))),
+            }
+        })
+        .collect())
+}
+
+fn parse_ascii(bytes: &[u8]) -> Result<Vec<Facet>, String> {
+    let source = std::str::from_utf8(bytes).map_err(|_| "ASCII file is not valid text".to_string())?;
+    let mut facets = Vec::new();
+    let mut normal = Vector::zero();
+    let mut vertices = Vec::with_capacity(3);
+    for (index, line) in source.lines().enumerate() {
+        let error = |message: String| format!("line {}: {message}", index + 1);
+        let tokens = line.split_whitespace().collect::<Vec<&str>>();
+        let parse_vector = |arguments: &[&str]| -> Result<Vector, String> {
+            if arguments.len() != 3 {
+                return Err(error(format!("expected 3 numbers, found {}", arguments.len())));
+            }
+            let mut values = [0.0; 3];
+            for (value, argument) in values.iter_mut().zip(arguments) {
+                *value = argument.parse().map_err(|_| error(format!("invalid number '{argument}'")))?;
+            }
+            Ok(Vector::from(values))
+        };
+        match tokens.as_slice() {
+            ["facet", "normal", arguments @ ..] => {
+                normal = parse_vector(arguments)?;
+                vertices.clear();
+            }
+            ["vertex", arguments @ ..] => vertices.push(parse_vector(arguments)?),
+            ["endfacet"] => {
+                let [a, b, c] = vertices[..] else {
+                    return Err(error(format!("facet has {} vertices, expected 3", vertices.len())));
+                };
+                facets.push(Facet {
+                    normal,
+                    vertices: [a, b, c],
+                    color: None,
+                });
+            }
+            _ => {}
+        }
+    }
+    Ok(facets)
+}
+
+/// Merges vertices at identical positions, averaging the normals of the faces around each
+/// vertex that lie within the crease angle of the face being shaded.
+fn weld(facets: &[Facet], crease_degrees: Float) -> TriangleMesh {
+    // `+ 0.0` folds negative zero into zero so both hash alike.
+    let key = |p: Vector| ((p.x + 0.0).to_bits(), (p.y + 0.0).to_bits(), (p.z + 0.0).to_bits());
+    let mut position_lookup = HashMap::new();
+    let mut positions = Vec::new();
+    let mut faces = Vec::with_capacity(facets.len());
+    let mut face_normals = Vec::with_capacity(facets.len());
+    for facet in facets {
+        let mut vertices = facet.vertices;
+        let mut face_normal = (vertices[1] - vertices[0]).cross(&(vertices[2] - vertices[0]));
+        // Trust the stored normal over the winding when the two disagree.
+        if face_normal.dot(&facet.normal) < 0.0 {
+            vertices.swap(1, 2);
+            face_normal = -face_normal;
+        }
+        faces.push(vertices.map(|vertex| {
+            *position_lookup.entry(key(vertex)).or_insert_with(|| {
+                positions.push(vertex);
+                positions.len() - 1
+            })
+        }));
+        face_normals.push(face_normal);
+    }
+
+    let mut adjacent_faces = vec![Vec::new(); positions.len()];
+    for (face, corners) in faces.iter().enumerate() {
+        for corner in corners {
+            adjacent_faces[*corner].push(face);
+        }
+    }
+
+    let cos_crease = crease_degrees.to_radians().cos();
+    let has_colors = facets.iter().any(|facet| facet.color.is_some());
+    let default_color = Vector::splat(0.8);
+    let mut vertex_lookup = HashMap::new();
+    let mut mesh_positions = Vec::new();
+    let mut normals = Vec::new();
+    let mut colors = Vec::new();
+    let mut indices = Vec::with_capacity(faces.len());
+    for (face, corners) in faces.iter().enumerate() {
+        let unit_normal = face_normals[face].normalize();
+        let color = facets[face].color.unwrap_or(default_color);
+        indices.push(corners.map(|corner| {
+            let normal = adjacent_faces[corner]
+                .iter()
+                .map(|other| face_normals[*other])
+                .filter(|other| other.normalize().dot(&unit_normal) >= cos_crease)
+                .fold(Vector::zero(), |sum, other| sum + other)
+                .normalize();
+            let normal = if normal.is_near_zero() { unit_normal } else { normal };
+            *vertex_lookup.entry((corner, key(normal), key(color))).or_insert_with(|| {
+                mesh_positions.push(positions[corner]);
+                normals.push(normal);
+                colors.push(color);
+                (mesh_positions.len() - 1) as u32
+            })
+        }));
+    }
+
+    let mut mesh = TriangleMesh::new(mesh_positions, indices, MaterialId::default()).with_normals(normals);
+    if has_colors {
+        mesh.colors = Some(colors);
+    }
+    mesh
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: Vector, expected: Vector) {
+        assert!((actual - expected).length() < 1e-5, "{actual:?} != {expected:?}");
+    }
+
+    /// Binary STL with one facet per entry of `facets`, given as vertices and attribute word.
+    fn binary(facets: &[([[f32; 3]; 3], u16)]) -> Vec<u8> {
+        // A header starting with "solid" must not fool the format check.
+        let mut bytes = b"solid binary".to_vec();
+        bytes.resize(80, 0);
+        bytes.extend((facets.len() as u32).to_le_bytes());
+        for (vertices, attribute) in facets {
+            bytes.extend([0.0f32; 3].iter().flat_map(|value| value.to_le_bytes()));
+            bytes.extend(vertices.iter().flatten().flat_map(|value| value.to_le_bytes()));
+            bytes.extend(attribute.to_le_bytes());
+        }
+        bytes
+    }
+
+    /// Two faces sharing the edge from the origin to (0, 0, 1), at a right angle: one in the
+    /// y = 0 plane facing -y and one in the x = 0 plane facing -x.
+    const TENT: &str = "solid tent
+  facet normal 0 -1 0
+    outer loop
+      vertex 0 0 0
+      vertex 1 0 0
+      vertex 0 0 1
+    endloop
+  endfacet
+  facet normal -1 0 0
+    outer loop
+      vertex 0 0 0
+      vertex 0 0 1
+      vertex 0 1 0
+    endloop
+  endfacet
+endsolid tent
+";
+
+    #[test]
+    fn parses_ascii() {
+        let mesh = parse_stl(TENT.as_bytes(), 180.0).unwrap();
+        assert_eq!(mesh.triangle_count(), 2);
+        assert_eq!(mesh.positions.len(), 4);
+        assert!(mesh.colors.is_none());
+
+        let error = parse_stl(b"solid bad\nfacet normal 0 0 1\nvertex 0 0\n", 180.0).err().unwrap();
+        assert_eq!(error, "line 3: expected 3 numbers, found 2");
+    }
+
+    #[test]
+    fn parses_binary_with_srgb_facet_colors() {
+        let triangle = [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]];
+        let shifted = triangle.map(|[x, y, z]| [x + 2.0, y, z]);
+        // Red 31, green 16 and blue 0, with the VisCAM color bit.
+        let bytes = binary(&[(triangle, 0x8000 | (31 << 10) | (16 << 5)), (shifted, 0)]);
+        let mesh = parse_stl(&bytes, 180.0).unwrap();
+        assert_eq!(mesh.triangle_count(), 2);
+        assert_eq!(mesh.positions.len(), 6);
+        let colors = mesh.colors.as_ref().unwrap();
+        let first = mesh.indices[0][0] as usize;
+        assert_close(colors[first], Vector::new(1.0, srgb_to_linear(16.0 / 31.0), 0.0));
+        let second = mesh.indices[1][0] as usize;
+        assert_close(colors[second], Vector::splat(0.8));
+
+        // Truncated files no longer match their facet count, so only a header not starting
+        // with "solid" marks them as binary.
+        let mut truncated = bytes[..bytes.len() - 10].to_vec();
+        truncated[..5].copy_from_slice(b"model");
+        let error = parse_stl(&truncated, 180.0).err().unwrap();
+        assert_eq!(error, "header declares 2 facets but the file holds 1");
+    }
+
+    #[test]
+    fn welds_normals_within_the_crease_angle() {
+        let normal_at = |mesh: &TriangleMesh, face: usize, position: Vector| {
+            let corner = mesh.indices[face].iter().find(|index| mesh.positions[**index as usize] == position).unwrap();
+            mesh.normals.as_ref().unwrap()[*corner as usize]
+        };
+        let origin = Vector::zero();
+
+        // The faces meet at 90 degrees, so a 60 degree crease keeps the shared edge sharp.
+        let sharp = parse_stl(TENT.as_bytes(), 60.0).unwrap();
+        assert_eq!(sharp.positions.len(), 6);
+        assert_close(normal_at(&sharp, 0, origin), Vector::new(0.0, -1.0, 0.0));
+        assert_close(normal_at(&sharp, 1, origin), Vector::new(-1.0, 0.0, 0.0));
+
+        let smooth = parse_stl(TENT.as_bytes(), 120.0).unwrap();
+        assert_eq!(smooth.positions.len(), 4);
+        let averaged = Vector::new(-1.0, -1.0, 0.0).normalize();
+        assert_close(normal_at(&smooth, 0, origin), averaged);
+        assert_close(normal_at(&smooth, 1, origin), averaged);
+        // Corners off the shared edge only touch one face.
+        assert_close(normal_at(&smooth, 0, Vector::new(1.0, 0.0, 0.0)), Vector::new(0.0, -1.0, 0.0));
+    }
+}
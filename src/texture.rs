@@ -0,0 +1,129 @@
+//! Image textures sampled by surface UV coordinates.
+
+use std::path::Path;
+
+use image::{DynamicImage, ImageError};
+
+use crate::math::Float;
+use crate::vector::Vector;
+
+/// Decodes an sRGB-encoded channel in `[0, 1]` to linear.
+pub fn srgb_to_linear(value: Float) -> Float {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Linear RGB texels with bilinear filtering. `u` runs left to right and `v` bottom to top,
+/// and both wrap around, as OBJ texture coordinates expect.
+pub struct ImageTexture {
+    width: usize,
+    height: usize,
+    texels: Vec<Vector>,
+}
+
+impl ImageTexture {
+    /// Color maps are usually sRGB-encoded; data such as bump maps should pass `srgb: false`.
+    pub fn from_image(image: &DynamicImage, srgb: bool) -> ImageTexture {
+        let rgb = image.to_rgb32f();
+        let decode = |value: f32| if srgb { srgb_to_linear(value as Float) } else { value as Float };
+        ImageTexture {
+            width: rgb.width() as usize,
+            height: rgb.height() as usize,
+            texels: rgb.pixels().map(|pixel| Vector::new(decode(pixel[0]), decode(pixel[1]), decode(pixel[2]))).collect(),
+        }
+    }
+
+    pub fn load(path: impl AsRef<Path>, srgb: bool) -> Result<ImageTexture, ImageError> {
+        Ok(ImageTexture::from_image(&image::open(path)?, srgb))
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    fn texel(&self, x: isize, y: isize) -> Vector {
+        let x = x.rem_euclid(self.width as isize) as usize;
+        let y = y.rem_euclid(self.height as isize) as usize;
+        self.texels[y * self.width + x]
+    }
+
+    pub fn sample(&self, u: Float, v: Float) -> Vector {
+        if self.texels.is_empty() {
+            return Vector::zero();
+        }
+        let x = u * self.width as Float - 0.5;
+        let y = (1.0 - v) * self.height as Float - 0.5;
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+        let (x0, y0) = (x0 as isize, y0 as isize);
+        let top = self.texel(x0, y0).lerp(&self.texel(x0 + 1, y0), fx);
+        let bottom = self.texel(x0, y0 + 1).lerp(&self.texel(x0 + 1, y0 + 1), fx);
+        top.lerp(&bottom, fy)
+    }
+
+    /// Height as the mean of the channels, for bump maps.
+    pub fn height_at(&self, u: Float, v: Float) -> Float {
+        let texel = self.sample(u, v);
+        (texel.x + texel.y + texel.z) / 3.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use image::RgbImage;
+
+    use super::*;
+
+    fn texture(width: u32, height: u32, texels: Vec<u8>, srgb: bool) -> ImageTexture {
+        ImageTexture::from_image(&DynamicImage::ImageRgb8(RgbImage::from_raw(width, height, texels).unwrap()), srgb)
+    }
+
+    fn assert_close(actual: Vector, expected: Vector) {
+        assert!((actual - expected).length() < 1e-6, "{actual:?} != {expected:?}");
+    }
+
+    #[test]
+    fn decodes_only_srgb_maps() {
+        assert_eq!(srgb_to_linear(0.0), 0.0);
+        assert!((srgb_to_linear(1.0) - 1.0).abs() < 1e-9);
+        assert!((srgb_to_linear(0.5) - 0.214041).abs() < 1e-6);
+        let gray = 128.0 / 255.0;
+        assert_close(texture(1, 1, vec![128; 3], true).sample(0.5, 0.5), Vector::splat(srgb_to_linear(gray)));
+        assert_close(texture(1, 1, vec![128; 3], false).sample(0.5, 0.5), Vector::splat(gray));
+    }
+
+    #[test]
+    fn v_runs_from_the_bottom_row_up() {
+        // Red on the top row, green on the bottom one.
+        let texture = texture(1, 2, vec![255, 0, 0, 0, 255, 0], false);
+        assert_close(texture.sample(0.5, 0.75), Vector::new(1.0, 0.0, 0.0));
+        assert_close(texture.sample(0.5, 0.25), Vector::new(0.0, 1.0, 0.0));
+        assert_close(texture.sample(0.5, 0.5), Vector::new(0.5, 0.5, 0.0));
+    }
+
+    #[test]
+    fn filters_between_texels_and_wraps() {
+        let texture = texture(2, 1, vec![0, 0, 0, 255, 255, 255], false);
+        assert_eq!((texture.width(), texture.height()), (2, 1));
+        assert_close(texture.sample(0.25, 0.5), Vector::zero());
+        assert_close(texture.sample(0.5, 0.5), Vector::splat(0.5));
+        assert_close(texture.sample(0.75, 0.5), Vector::splat(1.0));
+        // Past either edge the texture repeats, so u = 0 blends the last texel with the first.
+        assert_close(texture.sample(1.25, -3.5), Vector::zero());
+        assert_close(texture.sample(-0.25, 0.5), Vector::splat(1.0));
+        assert_close(texture.sample(0.0, 0.5), Vector::splat(0.5));
+    }
+
+    #[test]
+    fn height_is_the_channel_mean() {
+        let texture = texture(1, 1, vec![51, 153, 255], false);
+        assert!((texture.height_at(0.5, 0.5) - 0.6).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,178 @@
+//! Top-level acceleration structure over instances of shared geometry.
+
+use std::sync::OnceLock;
+
+use crate::aabb::Aabb;
+use crate::bvh::{Bvh, REBUILD_COST_RATIO};
+use crate::hittable::{HitRecord, Hittable, SurfaceSample};
+use crate::instance::Instance;
+use crate::math::Float;
+use crate::ray::Ray;
+use crate::vector::Vector;
+
+/// A BVH over instances, each pointing at geometry with its own bottom-level structure, such
+/// as a `TriangleMesh`. Memory grows with the unique geometry; an instance only adds its
+/// transform and world bounds, and moving instances only touches this level. Instanced
+/// geometry must be bounded; add planes to the scene directly.
+#[derive(Default)]
+pub struct Tlas {
+    instances: Vec<Instance>,
+    bvh: OnceLock<Bvh>,
+    /// Running sum of the estimated instance areas, for area sampling.
+    area_cdf: OnceLock<Vec<Float>>,
+}
+
+/// Samples per side of the grid that estimates an instance's area.
+const AREA_GRID: usize = 8;
+
+/// The instance's surface area, as the mean of `1 / pdf` over a grid of surface samples. It is
+/// exact when the pdf is uniform, and otherwise only has to be close: `sample_surface` reports
+/// the pdf of the same weights it picks with.
+fn estimated_area(instance: &Instance) -> Float {
+    let cell = |index: usize| (index as Float + 0.5) / AREA_GRID as Float;
+    let pdfs = (0..AREA_GRID * AREA_GRID).map(|index| instance.sample_surface(cell(index % AREA_GRID), cell(index / AREA_GRID)).pdf);
+    let inverse_pdfs = pdfs.map(|pdf| if pdf > 0.0 { 1.0 / pdf } else { 0.0 });
+    inverse_pdfs.sum::<Float>() / (AREA_GRID * AREA_GRID) as Float
+}
+
+impl Tlas {
+    pub fn new() -> Tlas {
+        Tlas {
+            instances: Vec::new(),
+            bvh: OnceLock::new(),
+            area_cdf: OnceLock::new(),
+        }
+    }
+
+    /// Returns the instance's index.
+    pub fn add(&mut self, instance: Instance) -> usize {
+        self.instances.push(instance);
+        self.bvh = OnceLock::new();
+        self.area_cdf = OnceLock::new();
+        self.instances.len() - 1
+    }
+
+    pub fn instances(&self) -> &[Instance] {
+        &self.instances
+    }
+
+    pub fn len(&self) -> usize {
+        self.instances.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.instances.is_empty()
+    }
+
+    /// Lets `edit` move instances, then refits the top level. The shared geometry and its
+    /// bottom-level structures are untouched. Returns whether the top level was rebuilt.
+    pub fn update(&mut self, edit: impl FnOnce(&mut [Instance])) -> bool {
+        edit(&mut self.instances);
+        self.area_cdf = OnceLock::new();
+        let bounds = self.instances.iter().map(Instance::bounding_box).collect::<Vec<Aabb>>();
+        match self.bvh.get_mut() {
+            Some(bvh) => bvh.refit_or_rebuild(&bounds, REBUILD_COST_RATIO),
+            None => false,
+        }
+    }
+
+    pub fn bvh(&self) -> &Bvh {
+        self.bvh.get_or_init(|| Bvh::build(&self.instances.iter().map(Instance::bounding_box).collect::<Vec<Aabb>>()))
+    }
+}
+
+impl Hittable for Tlas {
+    fn intersect(&self, ray: &Ray, t_min: Float, t_max: Float) -> Option<HitRecord> {
+        self.bvh().intersect(ray, t_min, t_max, |instance, t_max| self.instances[instance].intersect(ray, t_min, t_max))
+    }
+
+    fn occluded(&self, ray: &Ray, t_min: Float, t_max: Float) -> bool {
+        self.bvh().occluded(ray, t_min, t_max, |instance| self.instances[instance].occluded(ray, t_min, t_max))
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bvh().bounding_box()
+    }
+
+    /// Picks an instance in proportion to its estimated area, reusing `u` within it, as
+    /// `TriangleMesh` does for triangles. The area table is built on the first call after the
+    /// instances change. A TLAS without area returns a zero pdf.
+    fn sample_surface(&self, u: Float, v: Float) -> SurfaceSample {
+        let cdf = self.area_cdf.get_or_init(|| {
+            let mut total = 0.0;
+            self.instances
+                .iter()
+                .map(|instance| {
+                    total += estimated_area(instance);
+                    total
+                })
+                .collect()
+        });
+        let total = cdf.last().copied().unwrap_or(0.0);
+        if total <= 0.0 {
+            return SurfaceSample {
+                point: Vector::zero(),
+                normal: Vector::new(0.0, 0.0, 1.0),
+                pdf: 0.0,
+            };
+        }
+        let target = u * total;
+        let instance = cdf.partition_point(|sum| *sum <= target).min(cdf.len().saturating_sub(1));
+        let start = if instance > 0 { cdf[instance - 1] } else { 0.0 };
+        let area = cdf[instance] - start;
+        let u = if area > 0.0 { ((target - start) / area).clamp(0.0, 1.0) } else { 0.0 };
+        let sample = self.instances[instance].sample_surface(u, v);
+        SurfaceSample {
+            pdf: sample.pdf * area / total,
+            ..sample
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::hittable::Hittable;
+    use crate::material::MaterialId;
+    use crate::math::consts::PI;
+    use crate::sphere::Sphere;
+    use crate::transform::Transform;
+
+    #[test]
+    fn empty_tlas_is_never_hit_or_sampled() {
+        let tlas = Tlas::new();
+        let ray = Ray {
+            origin: Vector::zero(),
+            direction: Vector::new(0.0, 0.0, 1.0),
+        };
+        assert!(tlas.intersect(&ray, 0.0, Float::INFINITY).is_none());
+        assert!(!tlas.occluded(&ray, 0.0, Float::INFINITY));
+        assert_eq!(tlas.sample_surface(0.5, 0.5).pdf, 0.0);
+    }
+
+    #[test]
+    fn samples_instances_by_area() {
+        let sphere: Arc<dyn Hittable> = Arc::new(Sphere {
+            center: Vector::zero(),
+            radius: 1.0,
+            material: MaterialId::default(),
+        });
+        let mut tlas = Tlas::new();
+        tlas.add(Instance::new(sphere.clone(), Transform::translate(Vector::new(-5.0, 0.0, 0.0))));
+        let scaled = Transform::translate(Vector::new(5.0, 0.0, 0.0)) * Transform::scale(Vector::splat(3.0)).unwrap();
+        tlas.add(Instance::new(sphere, scaled));
+        // The spheres have areas 4π and 36π, so the larger one takes nine tenths of the samples
+        // and the whole surface has a uniform pdf of 1 / 40π.
+        for (u, x) in [(0.05, -5.0), (0.15, 5.0), (0.95, 5.0)] {
+            let sample = tlas.sample_surface(u, 0.5);
+            assert!((sample.point.x - x).abs() <= 3.0, "{u}: {:?}", sample.point);
+            assert!((sample.pdf - 1.0 / (40.0 * PI)).abs() < 1e-6, "{u}: {}", sample.pdf);
+        }
+
+        // Moving instances rebuilds the table.
+        tlas.update(|instances| instances[1].set_transform(Transform::translate(Vector::new(5.0, 0.0, 0.0))));
+        assert!((tlas.sample_surface(0.95, 0.5).pdf - 1.0 / (8.0 * PI)).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,152 @@
+use crate::aabb::Aabb;
+use crate::hittable::{HitRecord, Hittable, SurfaceSample};
+use crate::material::MaterialId;
+use crate::math::consts::PI;
+use crate::math::{to_f64, Float};
+use crate::ray::Ray;
+use crate::solver::solve_quartic;
+use crate::vector::Vector;
+
+/// Torus around the local y axis: a tube of `minor_radius` swept along a circle of `major_radius`.
+pub struct Torus {
+    pub major_radius: Float,
+    pub minor_radius: Float,
+    pub material: MaterialId,
+}
+
+impl Hittable for Torus {
+    fn intersect(&self, ray: &Ray, t_min: Float, t_max: Float) -> Option<HitRecord> {
+        if !self.bounding_box().hit(ray, t_min, t_max) {
+            return None;
+        }
+        let (big_r, small_r) = (to_f64(self.major_radius), to_f64(self.minor_radius));
+
+        // Solve with a unit direction from the point closest to the center to keep the
+        // quartic coefficients small, then map roots back to the caller's parameterization.
+        let length = to_f64(ray.direction.length());
+        let d = [to_f64(ray.direction.x) / length, to_f64(ray.direction.y) / length, to_f64(ray.direction.z) / length];
+        let o = [to_f64(ray.origin.x), to_f64(ray.origin.y), to_f64(ray.origin.z)];
+        let shift = -(o[0] * d[0] + o[1] * d[1] + o[2] * d[2]);
+        let o = [o[0] + d[0] * shift, o[1] + d[1] * shift, o[2] + d[2] * shift];
+
+        let m = o[0] * o[0] + o[1] * o[1] + o[2] * o[2];
+        let n = o[0] * d[0] + o[1] * d[1] + o[2] * d[2];
+        let k = m - big_r * big_r - small_r * small_r;
+        let four_r2 = 4.0 * big_r * big_r;
+        let roots = solve_quartic(
+            1.0,
+            4.0 * n,
+            4.0 * n * n + 2.0 * k + four_r2 * d[1] * d[1],
+            4.0 * n * k + 2.0 * four_r2 * o[1] * d[1],
+            k * k + four_r2 * (o[1] * o[1] - small_r * small_r),
+        );
+
+        let t = roots
+            .into_iter()
+            .map(|root| ((root + shift) / length) as Float)
+            .find(|t| *t > t_min && *t < t_max)?;
+
+        let point = ray.origin + ray.direction * t;
+        let ring = Vector::new(point.x, 0.0, point.z).normalize() * self.major_radius;
+        let outward_normal = (point - ring).normalize();
+        let (normal, front_face) = HitRecord::face_normal(ray, outward_normal);
+        let rho = (point.x * point.x + point.z * point.z).sqrt();
+        Some(HitRecord {
+            t,
+            point,
+            normal,
+            front_face,
+            u: (point.z.atan2(point.x) + PI) / (2.0 * PI),
+            v: (point.y.atan2(rho - self.major_radius) + PI) / (2.0 * PI),
+            material: self.material,
+            vertex_color: None,
+            tangent: None,
+        })
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        let outer = self.major_radius + self.minor_radius;
+        Aabb::new(
+            Vector::new(-outer, -self.minor_radius, -outer),
+            Vector::new(outer, self.minor_radius, outer),
+        )
+    }
+
+    /// Samples both angles uniformly; the pdf accounts for the area stretching on the outside.
+    fn sample_surface(&self, u: Float, v: Float) -> SurfaceSample {
+        let phi = 2.0 * PI * u;
+        let theta = 2.0 * PI * v;
+        let ring_distance = self.major_radius + self.minor_radius * theta.cos();
+        let normal = Vector::new(theta.cos() * phi.cos(), theta.sin(), theta.cos() * phi.sin());
+        SurfaceSample {
+            point: Vector::new(ring_distance * phi.cos(), self.minor_radius * theta.sin(), ring_distance * phi.sin()),
+            normal,
+            pdf: 1.0 / (4.0 * PI * PI * self.minor_radius * ring_distance),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn torus() -> Torus {
+        Torus {
+            major_radius: 2.0,
+            minor_radius: 0.5,
+            material: MaterialId::default(),
+        }
+    }
+
+    fn hit_distance(origin: Vector, direction: Vector) -> Option<Float> {
+        torus().intersect(&Ray { origin, direction }, 0.0, Float::INFINITY).map(|hit| hit.t)
+    }
+
+    fn assert_close(actual: Option<Float>, expected: Float) {
+        let actual = actual.expect("expected a hit");
+        assert!((actual - expected).abs() < 1e-4, "{actual} != {expected}");
+    }
+
+    #[test]
+    fn hits_outer_wall() {
+        assert_close(hit_distance(Vector::new(-10.0, 0.0, 0.0), Vector::new(1.0, 0.0, 0.0)), 7.5);
+    }
+
+    #[test]
+    fn hits_inner_wall_from_the_hole() {
+        assert_close(hit_distance(Vector::zero(), Vector::new(1.0, 0.0, 0.0)), 1.5);
+    }
+
+    #[test]
+    fn hits_top_of_tube() {
+        assert_close(hit_distance(Vector::new(2.0, 10.0, 0.0), Vector::new(0.0, -1.0, 0.0)), 9.5);
+    }
+
+    #[test]
+    fn measures_t_in_multiples_of_the_direction() {
+        assert_close(hit_distance(Vector::new(-10.0, 0.0, 0.0), Vector::new(2.0, 0.0, 0.0)), 3.75);
+    }
+
+    #[test]
+    fn hits_back_face_from_inside_the_tube() {
+        let ray = Ray {
+            origin: Vector::new(2.0, 0.0, 0.0),
+            direction: Vector::new(1.0, 0.0, 0.0),
+        };
+        let hit = torus().intersect(&ray, 0.0, Float::INFINITY).expect("expected a hit");
+        assert!((hit.t - 0.5).abs() < 1e-4);
+        assert!(!hit.front_face);
+    }
+
+    #[test]
+    fn grazes_top_of_tube() {
+        // Tangent to the tube top at x = -2 and x = 2, a double root of the quartic each time.
+        assert_close(hit_distance(Vector::new(-10.0, 0.5, 0.0), Vector::new(1.0, 0.0, 0.0)), 8.0);
+    }
+
+    #[test]
+    fn misses_above_and_through_the_hole() {
+        assert_eq!(hit_distance(Vector::new(-10.0, 0.6, 0.0), Vector::new(1.0, 0.0, 0.0)), None);
+        assert_eq!(hit_distance(Vector::new(0.0, 10.0, 0.0), Vector::new(0.0, -1.0, 0.0)), None);
+    }
+}
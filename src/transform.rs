@@ -0,0 +1,222 @@
+use std::ops::Mul;
+
+use cgmath::{InnerSpace, Matrix, SquareMatrix, Transform as _};
+
+use crate::aabb::Aabb;
+use crate::math::{Deg, Float, Matrix4, Point3, Quaternion, Vector3};
+use crate::ray::Ray;
+use crate::vector::Vector;
+
+/// Affine transform that keeps its inverse alongside the matrix.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Transform {
+    matrix: Matrix4,
+    inverse: Matrix4,
+}
+
+impl Transform {
+    pub fn identity() -> Transform {
+        Transform {
+            matrix: Matrix4::identity(),
+            inverse: Matrix4::identity(),
+        }
+    }
+
+    /// Returns `None` for singular matrices.
+    pub fn from_matrix(matrix: Matrix4) -> Option<Transform> {
+        Some(Transform {
+            matrix,
+            inverse: matrix.invert()?,
+        })
+    }
+
+    pub fn translate(offset: Vector) -> Transform {
+        Transform {
+            matrix: Matrix4::from_translation(offset.into()),
+            inverse: Matrix4::from_translation((-offset).into()),
+        }
+    }
+
+    /// Returns `None` when a factor is zero or not finite, like `from_matrix` for singular matrices.
+    pub fn scale(factors: Vector) -> Option<Transform> {
+        if [factors.x, factors.y, factors.z].iter().any(|factor| *factor == 0.0 || !factor.is_finite()) {
+            return None;
+        }
+        Some(Transform {
+            matrix: Matrix4::from_nonuniform_scale(factors.x, factors.y, factors.z),
+            inverse: Matrix4::from_nonuniform_scale(1.0 / factors.x, 1.0 / factors.y, 1.0 / factors.z),
+        })
+    }
+
+    /// Returns `None` for a zero axis, which has no direction to rotate about.
+    pub fn rotate(axis: Vector, degrees: Float) -> Option<Transform> {
+        if axis.is_near_zero() {
+            return None;
+        }
+        let matrix = Matrix4::from_axis_angle(Vector3::from(axis).normalize(), Deg(degrees));
+        Some(Transform {
+            matrix,
+            inverse: matrix.transpose(),
+        })
+    }
+
+    pub fn from_quaternion(rotation: Quaternion) -> Transform {
+        let matrix = Matrix4::from(rotation.normalize());
+        Transform {
+            matrix,
+            inverse: matrix.transpose(),
+        }
+    }
+
+    /// Places an object at `eye` with its local +z axis pointing at `target` and +y towards `up`.
+    /// Returns `None` when `eye` and `target` coincide or `up` is parallel to the view direction.
+    pub fn look_at(eye: Vector, target: Vector, up: Vector) -> Option<Transform> {
+        let z = (target - eye).normalize();
+        let x = up.cross(&z);
+        if z.is_near_zero() || x.is_near_zero() {
+            return None;
+        }
+        let x = x.normalize();
+        let y = z.cross(&x);
+        let matrix = Matrix4::new(
+            x.x, x.y, x.z, 0.0,
+            y.x, y.y, y.z, 0.0,
+            z.x, z.y, z.z, 0.0,
+            eye.x, eye.y, eye.z, 1.0,
+        );
+        Transform::from_matrix(matrix)
+    }
+
+    pub fn matrix(&self) -> Matrix4 {
+        self.matrix
+    }
+
+    pub fn inverse(&self) -> Transform {
+        Transform {
+            matrix: self.inverse,
+            inverse: self.matrix,
+        }
+    }
+
+    pub fn transform_point(&self, point: Vector) -> Vector {
+        self.matrix.transform_point(Point3::from(point)).into()
+    }
+
+    pub fn transform_vector(&self, vector: Vector) -> Vector {
+        self.matrix.transform_vector(vector.into()).into()
+    }
+
+    /// Transforms a surface normal by the inverse transpose; the result is not normalized.
+    pub fn transform_normal(&self, normal: Vector) -> Vector {
+        let n = Vector3::from(normal);
+        Vector::new(
+            self.inverse.x.truncate().dot(n),
+            self.inverse.y.truncate().dot(n),
+            self.inverse.z.truncate().dot(n),
+        )
+    }
+
+    /// Transforms origin and direction without renormalizing, so hit distances carry over.
+    pub fn transform_ray(&self, ray: &Ray) -> Ray {
+        Ray {
+            origin: self.transform_point(ray.origin),
+            direction: self.transform_vector(ray.direction),
+        }
+    }
+
+    pub fn transform_aabb(&self, bounds: &Aabb) -> Aabb {
+        let is_finite = |v: &Vector| v.x.is_finite() && v.y.is_finite() && v.z.is_finite();
+        if !is_finite(&bounds.min) || !is_finite(&bounds.max) {
+            return Aabb::infinite();
+        }
+        let mut result = Aabb::empty();
+        for corner in 0..8 {
+            let point = Vector::new(
+                if corner & 1 == 0 { bounds.min.x } else { bounds.max.x },
+                if corner & 2 == 0 { bounds.min.y } else { bounds.max.y },
+                if corner & 4 == 0 { bounds.min.z } else { bounds.max.z },
+            );
+            let point = self.transform_point(point);
+            result = result.union(&Aabb::new(point, point));
+        }
+        result
+    }
+
+    /// Ratio of transformed to original surface area at a point with unit normal `normal`.
+    pub fn area_scale(&self, normal: Vector) -> Float {
+        let determinant = self.matrix.determinant().abs();
+        determinant * self.transform_normal(normal).length()
+    }
+}
+
+/// `a * b` applies `b` first, then `a`.
+impl Mul for Transform {
+    type Output = Transform;
+
+    fn mul(self, rhs: Transform) -> Transform {
+        Transform {
+            matrix: self.matrix * rhs.matrix,
+            inverse: rhs.inverse * self.inverse,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: Vector, expected: Vector) {
+        assert!((actual - expected).length() < 1e-5, "{actual:?} != {expected:?}");
+    }
+
+    fn skewed() -> Transform {
+        Transform::translate(Vector::new(1.0, -2.0, 3.0))
+            * Transform::rotate(Vector::new(1.0, 1.0, 0.0), 30.0).unwrap()
+            * Transform::scale(Vector::new(1.0, 4.0, 0.5)).unwrap()
+    }
+
+    #[test]
+    fn normals_stay_perpendicular_under_non_uniform_scale() {
+        let transform = skewed();
+        let normal = Vector::new(1.0, 1.0, 1.0).normalize();
+        let (tangent, bitangent) = normal.orthonormal_basis();
+        let transformed = transform.transform_normal(normal);
+        for vector in [tangent, bitangent] {
+            let vector = transform.transform_vector(vector);
+            assert!(transformed.dot(&vector).abs() < 1e-5, "{transformed:?} . {vector:?}");
+        }
+        // Transforming the vector itself would tilt it off the surface.
+        assert!(transform.transform_vector(normal).dot(&transform.transform_vector(tangent)).abs() > 0.1);
+    }
+
+    #[test]
+    fn inverse_round_trips_points() {
+        let transform = skewed();
+        let point = Vector::new(0.3, -1.7, 2.5);
+        assert_close(transform.inverse().transform_point(transform.transform_point(point)), point);
+        assert_close(transform.transform_point(transform.inverse().transform_point(point)), point);
+    }
+
+    #[test]
+    fn look_at_aims_local_z_at_the_target() {
+        let eye = Vector::new(1.0, 2.0, 3.0);
+        let transform = Transform::look_at(eye, Vector::new(1.0, 2.0, -7.0), Vector::new(0.0, 1.0, 0.0)).unwrap();
+        assert_close(transform.transform_point(Vector::zero()), eye);
+        assert_close(transform.transform_vector(Vector::new(0.0, 0.0, 1.0)), Vector::new(0.0, 0.0, -1.0));
+        assert_close(transform.transform_vector(Vector::new(0.0, 1.0, 0.0)), Vector::new(0.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn rejects_degenerate_frames() {
+        let up = Vector::new(0.0, 1.0, 0.0);
+        assert!(Transform::look_at(Vector::zero(), Vector::zero(), up).is_none());
+        assert!(Transform::look_at(Vector::zero(), Vector::new(0.0, 5.0, 0.0), up).is_none());
+        assert!(Transform::rotate(Vector::zero(), 45.0).is_none());
+    }
+
+    #[test]
+    fn rejects_zero_scale() {
+        assert!(Transform::scale(Vector::new(1.0, 0.0, 2.0)).is_none());
+        assert!(Transform::scale(Vector::new(1.0, Float::INFINITY, 2.0)).is_none());
+    }
+}
@@ -0,0 +1,361 @@
+use std::ops::{Add, AddAssign, Div, DivAssign, Index, IndexMut, Mul, MulAssign, Neg, Sub, SubAssign};
+
+use crate::math::Float;
+
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Vector {
+    pub x: Float,
+    pub y: Float,
+    pub z: Float,
+}
+
+impl Add<Vector> for Vector {
+    type Output = Vector;
+
+    fn add(self, rhs: Vector) -> Vector {
+        Vector {
+            x: self.x + rhs.x,
+            y: self.y + rhs.y,
+            z: self.z + rhs.z,
+        }
+    }
+}
+
+impl AddAssign for Vector {
+    fn add_assign(&mut self, rhs: Vector) {
+        *self = *self + rhs;
+    }
+}
+
+impl Mul<Float> for Vector {
+    type Output = Vector;
+
+    fn mul(self, rhs: Float) -> Vector {
+        Vector {
+            x: self.x * rhs,
+            y: self.y * rhs,
+            z: self.z * rhs,
+        }
+    }
+}
+
+impl Mul<Vector> for Float {
+    type Output = Vector;
+
+    fn mul(self, rhs: Vector) -> Vector {
+        rhs * self
+    }
+}
+
+/// Component-wise product, used to modulate colors.
+impl Mul<Vector> for Vector {
+    type Output = Vector;
+
+    fn mul(self, rhs: Vector) -> Vector {
+        Vector {
+            x: self.x * rhs.x,
+            y: self.y * rhs.y,
+            z: self.z * rhs.z,
+        }
+    }
+}
+
+impl MulAssign<Float> for Vector {
+    fn mul_assign(&mut self, rhs: Float) {
+        *self = *self * rhs;
+    }
+}
+
+impl MulAssign<Vector> for Vector {
+    fn mul_assign(&mut self, rhs: Vector) {
+        *self = *self * rhs;
+    }
+}
+
+impl Div<Float> for Vector {
+    type Output = Vector;
+
+    fn div(self, rhs: Float) -> Vector {
+        Vector {
+            x: self.x / rhs,
+            y: self.y / rhs,
+            z: self.z / rhs,
+        }
+    }
+}
+
+/// Component-wise quotient.
+impl Div<Vector> for Vector {
+    type Output = Vector;
+
+    fn div(self, rhs: Vector) -> Vector {
+        Vector {
+            x: self.x / rhs.x,
+            y: self.y / rhs.y,
+            z: self.z / rhs.z,
+        }
+    }
+}
+
+impl DivAssign<Float> for Vector {
+    fn div_assign(&mut self, rhs: Float) {
+        *self = *self / rhs;
+    }
+}
+
+impl Sub for Vector {
+    type Output = Vector;
+
+    fn sub(self, other: Vector) -> Vector {
+        Vector {
+            x: self.x - other.x,
+            y: self.y - other.y,
+            z: self.z - other.z,
+        }
+    }
+}
+
+impl SubAssign for Vector {
+    fn sub_assign(&mut self, rhs: Vector) {
+        *self = *self - rhs;
+    }
+}
+
+impl Neg for Vector {
+    type Output = Vector;
+
+    fn neg(self) -> Vector {
+        Vector {
+            x: -self.x,
+            y: -self.y,
+            z: -self.z,
+        }
+    }
+}
+
+impl From<[Float; 3]> for Vector {
+    fn from(v: [Float; 3]) -> Vector {
+        Vector::new(v[0], v[1], v[2])
+    }
+}
+
+impl Index<usize> for Vector {
+    type Output = Float;
+
+    fn index(&self, axis: usize) -> &Float {
+        match axis {
+            0 => &self.x,
+            1 => &self.y,
+            2 => &self.z,
+            _ => panic!("Vector axis {axis} out of range"),
+        }
+    }
+}
+
+impl IndexMut<usize> for Vector {
+    fn index_mut(&mut self, axis: usize) -> &mut Float {
+        match axis {
+            0 => &mut self.x,
+            1 => &mut self.y,
+            2 => &mut self.z,
+            _ => panic!("Vector axis {axis} out of range"),
+        }
+    }
+}
+
+impl Vector {
+    pub fn new(x: Float, y: Float, z: Float) -> Vector {
+        Vector { x, y, z }
+    }
+
+    pub fn splat(value: Float) -> Vector {
+        Vector::new(value, value, value)
+    }
+
+    pub fn zero() -> Vector {
+        Vector::splat(0.0)
+    }
+
+    /// Returns the zero vector for zero-length input instead of NaNs.
+    pub fn normalize(&self) -> Vector {
+        let length = self.length();
+        if length == 0.0 {
+            return Vector::zero();
+        }
+        *self / length
+    }
+
+    pub fn dot(&self, other: &Vector) -> Float {
+        self.x * other.x + self.y * other.y + self.z * other.z
+    }
+
+    pub fn cross(&self, other: &Vector) -> Vector {
+        Vector {
+            x: self.y * other.z - self.z * other.y,
+            y: self.z * other.x - self.x * other.z,
+            z: self.x * other.y - self.y * other.x,
+        }
+    }
+
+    pub fn length_squared(&self) -> Float {
+        self.dot(self)
+    }
+
+    pub fn length(&self) -> Float {
+        self.length_squared().sqrt()
+    }
+
+    pub fn min(&self, other: &Vector) -> Vector {
+        Vector::new(self.x.min(other.x), self.y.min(other.y), self.z.min(other.z))
+    }
+
+    pub fn max(&self, other: &Vector) -> Vector {
+        Vector::new(self.x.max(other.x), self.y.max(other.y), self.z.max(other.z))
+    }
+
+    pub fn min_component(&self) -> Float {
+        self.x.min(self.y).min(self.z)
+    }
+
+    pub fn max_component(&self) -> Float {
+        self.x.max(self.y).max(self.z)
+    }
+
+    pub fn abs(&self) -> Vector {
+        Vector::new(self.x.abs(), self.y.abs(), self.z.abs())
+    }
+
+    pub fn lerp(&self, other: &Vector, t: Float) -> Vector {
+        *self + (*other - *self) * t
+    }
+
+    /// Mirrors the direction about `normal`, which must be unit length.
+    pub fn reflect(&self, normal: &Vector) -> Vector {
+        *self - *normal * (2.0 * self.dot(normal))
+    }
+
+    /// Bends a unit direction through a surface whose unit `normal` faces against it, with
+    /// `eta_ratio` the incident over transmitted index of refraction. Returns `None` on total
+    /// internal reflection.
+    pub fn refract(&self, normal: &Vector, eta_ratio: Float) -> Option<Vector> {
+        let cos_i = (-self.dot(normal)).min(1.0);
+        let sin2_t = eta_ratio * eta_ratio * (1.0 - cos_i * cos_i);
+        if sin2_t > 1.0 {
+            return None;
+        }
+        let cos_t = (1.0 - sin2_t).sqrt();
+        Some(*self * eta_ratio + *normal * (eta_ratio * cos_i - cos_t))
+    }
+
+    /// Two unit vectors that form a right-handed orthonormal basis with this unit vector.
+    pub fn orthonormal_basis(&self) -> (Vector, Vector) {
+        let sign = if self.z >= 0.0 { 1.0 } else { -1.0 };
+        let a = -1.0 / (sign + self.z);
+        let b = self.x * self.y * a;
+        (
+            Vector::new(1.0 + sign * self.x * self.x * a, sign * b, -sign * self.x),
+            Vector::new(b, sign + self.y * self.y * a, -self.y),
+        )
+    }
+
+    pub fn is_near_zero(&self) -> bool {
+        const EPSILON: Float = 1e-8;
+        self.x.abs() < EPSILON && self.y.abs() < EPSILON && self.z.abs() < EPSILON
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: Vector, b: Vector) {
+        assert!((a - b).length() < 1e-5, "{a:?} != {b:?}");
+    }
+
+    #[test]
+    fn cross_follows_right_hand_rule() {
+        let (x, y, z) = (Vector::new(1.0, 0.0, 0.0), Vector::new(0.0, 1.0, 0.0), Vector::new(0.0, 0.0, 1.0));
+        assert_eq!(x.cross(&y), z);
+        assert_eq!(y.cross(&z), x);
+        assert_eq!(y.cross(&x), -z);
+        assert_eq!(Vector::new(1.0, 2.0, 3.0).cross(&Vector::new(4.0, 5.0, 6.0)), Vector::new(-3.0, 6.0, -3.0));
+    }
+
+    #[test]
+    fn reflect_flips_normal_component() {
+        let normal = Vector::new(0.0, 1.0, 0.0);
+        assert_eq!(Vector::new(1.0, -1.0, 0.0).reflect(&normal), Vector::new(1.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn refract_obeys_snell() {
+        let normal = Vector::new(0.0, 1.0, 0.0);
+        let incident = Vector::new(1.0, -1.0, 0.0).normalize();
+        let refracted = incident.refract(&normal, 1.0 / 1.5).unwrap();
+        assert!((refracted.length() - 1.0).abs() < 1e-5);
+        let (sin_i, sin_t) = (incident.x, refracted.x);
+        assert!((sin_i - 1.5 * sin_t).abs() < 1e-5);
+        assert!(refracted.y < 0.0);
+
+        // Straight through at normal incidence, whatever the ratio.
+        assert_close(Vector::new(0.0, -1.0, 0.0).refract(&normal, 1.5).unwrap(), Vector::new(0.0, -1.0, 0.0));
+    }
+
+    #[test]
+    fn refract_returns_none_under_total_internal_reflection() {
+        let normal = Vector::new(0.0, 1.0, 0.0);
+        let grazing = Vector::new(1.0, -0.2, 0.0).normalize();
+        assert_eq!(grazing.refract(&normal, 1.5), None);
+    }
+
+    #[test]
+    fn lerp_interpolates_endpoints() {
+        let (a, b) = (Vector::new(0.0, 2.0, -4.0), Vector::new(2.0, 4.0, 0.0));
+        assert_eq!(a.lerp(&b, 0.0), a);
+        assert_eq!(a.lerp(&b, 1.0), b);
+        assert_eq!(a.lerp(&b, 0.5), Vector::new(1.0, 3.0, -2.0));
+    }
+
+    #[test]
+    fn index_reads_and_writes_axes() {
+        let mut v = Vector::new(1.0, 2.0, 3.0);
+        assert_eq!([v[0], v[1], v[2]], [1.0, 2.0, 3.0]);
+        v[1] = 5.0;
+        assert_eq!(v.y, 5.0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn index_out_of_range_panics() {
+        let _ = Vector::zero()[3];
+    }
+
+    #[test]
+    fn arithmetic_operators() {
+        let mut v = Vector::new(2.0, -4.0, 8.0);
+        assert_eq!(v / 2.0, Vector::new(1.0, -2.0, 4.0));
+        assert_eq!(v / Vector::new(2.0, 4.0, 8.0), Vector::new(1.0, -1.0, 1.0));
+        assert_eq!(-v, Vector::new(-2.0, 4.0, -8.0));
+        v += Vector::new(1.0, 1.0, 1.0);
+        assert_eq!(v, Vector::new(3.0, -3.0, 9.0));
+    }
+
+    #[test]
+    fn orthonormal_basis_is_orthonormal() {
+        for normal in [Vector::new(0.0, 0.0, 1.0), Vector::new(0.0, 0.0, -1.0), Vector::new(1.0, 2.0, -3.0).normalize(), Vector::new(-0.3, 0.1, 0.9).normalize()] {
+            let (tangent, bitangent) = normal.orthonormal_basis();
+            for (a, b) in [(tangent, bitangent), (tangent, normal), (bitangent, normal)] {
+                assert!(a.dot(&b).abs() < 1e-5);
+            }
+            assert!((tangent.length() - 1.0).abs() < 1e-5);
+            assert!((bitangent.length() - 1.0).abs() < 1e-5);
+            assert_close(tangent.cross(&bitangent), normal);
+        }
+    }
+
+    #[test]
+    fn normalize_zero_is_zero() {
+        assert_eq!(Vector::zero().normalize(), Vector::zero());
+        assert_close(Vector::new(3.0, 0.0, 4.0).normalize(), Vector::new(0.6, 0.0, 0.8));
+    }
+}
@@ -0,0 +1,467 @@
+//! Four-wide BVH collapsed from a binary `Bvh`, testing all four child boxes of a node at once.
+//!
+//! Boxes are stored as `f32` lanes and tested with SSE on x86_64, or a scalar loop elsewhere.
+
+use crate::aabb::Aabb;
+use crate::bvh::Bvh;
+use crate::hittable::HitRecord;
+use crate::math::{to_f32, Float};
+use crate::ray::Ray;
+use crate::vector::Vector;
+
+pub const WIDTH: usize = 4;
+const STACK_SIZE: usize = 384;
+/// Padding of the `f32` boxes relative to the largest root coordinate, covering the rounding of
+/// the bounds and of ray origins within the scene.
+const BOX_PADDING: f32 = 4.0 * f32::EPSILON;
+/// Relative widening of each child's entry and exit distances, covering the rounding of ray
+/// origins far outside the scene, of the direction inverse and of the slab arithmetic.
+const DISTANCE_PADDING: f32 = 8.0 * f32::EPSILON;
+/// Marks an unused slot in `WideBvh::sources`.
+const NO_SOURCE: u32 = u32::MAX;
+
+/// Bounds are stored per axis with one lane per child. A child is a leaf with `count`
+/// primitives starting at `offset`, or, when `count` is zero, the wide node at `offset`.
+/// Unused slots have an empty box, which no ray enters.
+#[derive(Copy, Clone)]
+#[repr(C, align(16))]
+pub struct WideNode {
+    pub min: [[f32; WIDTH]; 3],
+    pub max: [[f32; WIDTH]; 3],
+    pub offset: [u32; WIDTH],
+    pub count: [u32; WIDTH],
+}
+
+impl WideNode {
+    fn empty() -> WideNode {
+        WideNode {
+            min: [[f32::INFINITY; WIDTH]; 3],
+            max: [[f32::NEG_INFINITY; WIDTH]; 3],
+            offset: [0; WIDTH],
+            count: [0; WIDTH],
+        }
+    }
+
+    fn set_bounds(&mut self, slot: usize, bounds: &Aabb, padding: f32) {
+        for axis in 0..3 {
+            self.min[axis][slot] = to_f32(bounds.min[axis]) - padding;
+            self.max[axis][slot] = to_f32(bounds.max[axis]) + padding;
+        }
+    }
+
+    fn bounds(&self, slot: usize) -> Aabb {
+        let vector = |values: &[[f32; WIDTH]; 3]| Vector::new(values[0][slot] as Float, values[1][slot] as Float, values[2][slot] as Float);
+        Aabb::new(vector(&self.min), vector(&self.max))
+    }
+}
+
+/// Ray data shared by the node tests of one traversal.
+struct WideRay {
+    origin: [f32; 3],
+    inv_direction: [f32; 3],
+    negative: [bool; 3],
+}
+
+impl WideRay {
+    fn new(ray: &Ray) -> WideRay {
+        let inv_direction = [0, 1, 2].map(|axis| 1.0 / to_f32(ray.direction[axis]));
+        WideRay {
+            origin: [0, 1, 2].map(|axis| to_f32(ray.origin[axis])),
+            inv_direction,
+            negative: inv_direction.map(|inv| inv < 0.0),
+        }
+    }
+}
+
+/// Entry distances of the four children and a bit mask of those the ray enters.
+#[cfg(target_arch = "x86_64")]
+fn intersect_node(node: &WideNode, ray: &WideRay, t_min: f32, t_max: f32) -> ([f32; WIDTH], u32) {
+    use std::arch::x86_64::*;
+
+    // SAFETY: SSE is part of the x86_64 baseline, and every load reads a whole `[f32; 4]`.
+    unsafe {
+        let mut t_near = _mm_set1_ps(t_min);
+        let mut t_far = _mm_set1_ps(t_max);
+        for axis in 0..3 {
+            let (near, far) = if ray.negative[axis] { (&node.max[axis], &node.min[axis]) } else { (&node.min[axis], &node.max[axis]) };
+            let origin = _mm_set1_ps(ray.origin[axis]);
+            let inv_direction = _mm_set1_ps(ray.inv_direction[axis]);
+            let t0 = _mm_mul_ps(_mm_sub_ps(_mm_loadu_ps(near.as_ptr()), origin), inv_direction);
+            let t1 = _mm_mul_ps(_mm_sub_ps(_mm_loadu_ps(far.as_ptr()), origin), inv_direction);
+            // `max`/`min` return their second operand for NaN, so a NaN never clips the interval.
+            t_near = _mm_max_ps(t0, t_near);
+            t_far = _mm_min_ps(t1, t_far);
+        }
+        let sign_bit = _mm_set1_ps(-0.0);
+        let padding = _mm_set1_ps(DISTANCE_PADDING);
+        t_near = _mm_sub_ps(t_near, _mm_mul_ps(_mm_andnot_ps(sign_bit, t_near), padding));
+        t_far = _mm_add_ps(t_far, _mm_mul_ps(_mm_andnot_ps(sign_bit, t_far), padding));
+        let mask = _mm_movemask_ps(_mm_cmple_ps(t_near, t_far)) as u32;
+        let mut distances = [0.0; WIDTH];
+        _mm_storeu_ps(distances.as_mut_ptr(), t_near);
+        (distances, mask)
+    }
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+fn intersect_node(node: &WideNode, ray: &WideRay, t_min: f32, t_max: f32) -> ([f32; WIDTH], u32) {
+    let mut distances = [0.0; WIDTH];
+    let mut mask = 0;
+    for (slot, distance) in distances.iter_mut().enumerate() {
+        let mut t_near = t_min;
+        let mut t_far = t_max;
+        for axis in 0..3 {
+            let (near, far) = if ray.negative[axis] { (node.max[axis][slot], node.min[axis][slot]) } else { (node.min[axis][slot], node.max[axis][slot]) };
+            let t0 = (near - ray.origin[axis]) * ray.inv_direction[axis];
+            let t1 = (far - ray.origin[axis]) * ray.inv_direction[axis];
+            if t0 > t_near {
+                t_near = t0;
+            }
+            if t1 < t_far {
+                t_far = t1;
+            }
+        }
+        t_near -= t_near.abs() * DISTANCE_PADDING;
+        t_far += t_far.abs() * DISTANCE_PADDING;
+        *distance = t_near;
+        if t_near <= t_far {
+            mask |= 1 << slot;
+        }
+    }
+    (distances, mask)
+}
+
+pub struct WideBvh {
+    nodes: Vec<WideNode>,
+    /// Binary node behind each slot, so refits can copy its bounds.
+    sources: Vec<[u32; WIDTH]>,
+    primitives: Vec<u32>,
+    bounds: Aabb,
+    padding: f32,
+}
+
+/// Absolute box padding for a tree with root `bounds`.
+fn box_padding(bounds: &Aabb) -> f32 {
+    let scale = (0..3).map(|axis| bounds.min[axis].abs().max(bounds.max[axis].abs())).fold(0.0, Float::max);
+    to_f32(scale) * BOX_PADDING + f32::MIN_POSITIVE
+}
+
+impl WideBvh {
+    /// Collapses a binary BVH by pulling up grandchildren, largest boxes first, until each node
+    /// has four children. Leaves and primitive order are kept.
+    pub fn from_bvh(bvh: &Bvh) -> WideBvh {
+        let mut wide = WideBvh {
+            nodes: Vec::with_capacity(bvh.nodes().len() / 2 + 1),
+            sources: Vec::with_capacity(bvh.nodes().len() / 2 + 1),
+            primitives: bvh.primitives().to_vec(),
+            bounds: bvh.bounding_box(),
+            padding: box_padding(&bvh.bounding_box()),
+        };
+        if bvh.primitives().is_empty() {
+            wide.nodes.push(WideNode::empty());
+            wide.sources.push([NO_SOURCE; WIDTH]);
+        } else if bvh.nodes()[0].is_leaf() {
+            let mut root = WideNode::empty();
+            root.set_bounds(0, &bvh.nodes()[0].bounds, wide.padding);
+            root.offset[0] = bvh.nodes()[0].offset;
+            root.count[0] = bvh.nodes()[0].count;
+            wide.nodes.push(root);
+            wide.sources.push([0, NO_SOURCE, NO_SOURCE, NO_SOURCE]);
+        } else {
+            wide.collapse(bvh, 0);
+        }
+        wide
+    }
+
+    fn collapse(&mut self, bvh: &Bvh, index: usize) -> usize {
+        let nodes = bvh.nodes();
+        let mut children = vec![index + 1, nodes[index].offset as usize];
+        while children.len() < WIDTH {
+            let largest = children
+                .iter()
+                .enumerate()
+                .filter(|(_, child)| !nodes[**child].is_leaf())
+                .max_by(|(_, a), (_, b)| nodes[**a].bounds.surface_area().total_cmp(&nodes[**b].bounds.surface_area()));
+            let Some((slot, &child)) = largest else {
+                break;
+            };
+            children[slot] = child + 1;
+            children.push(nodes[child].offset as usize);
+        }
+
+        let wide_index = self.nodes.len();
+        self.nodes.push(WideNode::empty());
+        self.sources.push([NO_SOURCE; WIDTH]);
+        for (slot, child) in children.into_iter().enumerate() {
+            let (offset, count) = if nodes[child].is_leaf() {
+                (nodes[child].offset, nodes[child].count)
+            } else {
+                (self.collapse(bvh, child) as u32, 0)
+            };
+            let node = &mut self.nodes[wide_index];
+            node.set_bounds(slot, &nodes[child].bounds, self.padding);
+            node.offset[slot] = offset;
+            node.count[slot] = count;
+            self.sources[wide_index][slot] = child as u32;
+        }
+        wide_index
+    }
+
+    /// Copies the bounds of a refitted `bvh`, which must be the tree this one was collapsed
+    /// from; after a rebuild, collapse the new tree with `from_bvh` instead.
+    pub fn refit(&mut self, bvh: &Bvh) {
+        self.bounds = bvh.bounding_box();
+        self.padding = box_padding(&self.bounds);
+        for (node, sources) in self.nodes.iter_mut().zip(&self.sources) {
+            for (slot, source) in sources.iter().enumerate() {
+                if *source != NO_SOURCE {
+                    node.set_bounds(slot, &bvh.nodes()[*source as usize].bounds, self.padding);
+                }
+            }
+        }
+    }
+
+    pub fn nodes(&self) -> &[WideNode] {
+        &self.nodes
+    }
+
+    pub fn bounding_box(&self) -> Aabb {
+        self.bounds
+    }
+
+    /// Bounds of the child in `slot` of node `index`, as the padded `f32` box that is tested.
+    pub fn child_bounds(&self, index: usize, slot: usize) -> Aabb {
+        self.nodes[index].bounds(slot)
+    }
+
+    fn leaf_primitives(&self, offset: u32, count: u32) -> &[u32] {
+        &self.primitives[offset as usize..(offset + count) as usize]
+    }
+
+    /// Closest hit, with the same contract as `Bvh::intersect`.
+    pub fn intersect(
+        &self,
+        ray: &Ray,
+        t_min: Float,
+        t_max: Float,
+        mut intersect_primitive: impl FnMut(usize, Float) -> Option<HitRecord>,
+    ) -> Option<HitRecord> {
+        let wide_ray = WideRay::new(ray);
+        let mut closest = None;
+        let mut t_max = t_max;
+        let mut stack = [(0u32, 0.0f32); STACK_SIZE];
+        let mut stack_size = 1;
+        while stack_size > 0 {
+            stack_size -= 1;
+            let (index, t_entry) = stack[stack_size];
+            if t_entry as Float > t_max {
+                continue;
+            }
+            let node = &self.nodes[index as usize];
+            let (distances, mut mask) = intersect_node(node, &wide_ray, to_f32(t_min), to_f32(t_max));
+
+            // Visit hit children nearest first: leaves now, interior nodes via the stack.
+            let mut order = [(0.0f32, 0usize); WIDTH];
+            let mut hits = 0;
+            while mask != 0 {
+                let slot = mask.trailing_zeros() as usize;
+                mask &= mask - 1;
+                let mut position = hits;
+                while position > 0 && order[position - 1].0 > distances[slot] {
+                    order[position] = order[position - 1];
+                    position -= 1;
+                }
+                order[position] = (distances[slot], slot);
+                hits += 1;
+            }
+            for &(distance, slot) in order[..hits].iter().rev() {
+                if node.count[slot] == 0 {
+                    stack[stack_size] = (node.offset[slot], distance);
+                    stack_size += 1;
+                }
+            }
+            for &(distance, slot) in &order[..hits] {
+                if node.count[slot] == 0 || distance as Float > t_max {
+                    continue;
+                }
+                for primitive in self.leaf_primitives(node.offset[slot], node.count[slot]) {
+                    if let Some(hit) = intersect_primitive(*primitive as usize, t_max) {
+                        t_max = hit.t;
+                        closest = Some(hit);
+                    }
+                }
+            }
+        }
+        closest
+    }
+
+    /// Any hit, with the same contract as `Bvh::occluded`.
+    pub fn occluded(&self, ray: &Ray, t_min: Float, t_max: Float, mut occludes: impl FnMut(usize) -> bool) -> bool {
+        let wide_ray = WideRay::new(ray);
+        let mut stack = [0u32; STACK_SIZE];
+        let mut stack_size = 1;
+        while stack_size > 0 {
+            stack_size -= 1;
+            let node = &self.nodes[stack[stack_size] as usize];
+            let (_, mut mask) = intersect_node(node, &wide_ray, to_f32(t_min), to_f32(t_max));
+            while mask != 0 {
+                let slot = mask.trailing_zeros() as usize;
+                mask &= mask - 1;
+                if node.count[slot] == 0 {
+                    stack[stack_size] = node.offset[slot];
+                    stack_size += 1;
+                } else if self.leaf_primitives(node.offset[slot], node.count[slot]).iter().any(|primitive| occludes(*primitive as usize)) {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::SmallRng;
+    use rand::{Rng, SeedableRng};
+
+    use super::*;
+    use crate::hittable::Hittable;
+    use crate::material::MaterialId;
+    use crate::sphere::Sphere;
+
+    /// Thin and flat boxes clustered far from the world origin.
+    fn far_boxes(rng: &mut SmallRng) -> Vec<Aabb> {
+        let center = Vector::new(1.0e4, -2.0e4, 3.0e4);
+        (0..2000)
+            .map(|index| {
+                let min = center + Vector::new(rng.gen_range(-100.0..100.0), rng.gen_range(-100.0..100.0), rng.gen_range(-100.0..100.0));
+                let mut size = Vector::new(rng.gen_range(0.0..2.0), rng.gen_range(0.0..2.0), rng.gen_range(0.0..2.0));
+                if index % 3 == 0 {
+                    size[index % 2] = 0.0;
+                }
+                Aabb::new(min, min + size)
+            })
+            .collect()
+    }
+
+    fn hit_box(bounds: &[Aabb], primitive: usize, ray: &Ray, t_min: Float, t_max: Float) -> Option<HitRecord> {
+        let inv_direction = Vector::new(1.0 / ray.direction.x, 1.0 / ray.direction.y, 1.0 / ray.direction.z);
+        let t = bounds[primitive].hit_distance(&ray.origin, &inv_direction, t_min, t_max)?;
+        Some(HitRecord {
+            t,
+            point: ray.origin + ray.direction * t,
+            normal: Vector::zero(),
+            front_face: true,
+            u: primitive as Float,
+            v: 0.0,
+            material: MaterialId::default(),
+            vertex_color: None,
+            tangent: None,
+        })
+    }
+
+    /// Rays from far away aimed at points inside random boxes, some along an axis.
+    fn far_rays(bounds: &[Aabb], rng: &mut SmallRng) -> Vec<Ray> {
+        (0..2000)
+            .map(|index| {
+                let target_box = &bounds[rng.gen_range(0..bounds.len())];
+                let target = target_box.min + (target_box.max - target_box.min) * rng.gen_range(0.0..1.0);
+                let offset = if index % 4 == 0 {
+                    let mut axis = Vector::zero();
+                    axis[index % 3] = if rng.gen() { 1.0e6 } else { -1.0e6 };
+                    axis
+                } else {
+                    Vector::new(rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0)).normalize() * 1.0e6
+                };
+                Ray {
+                    origin: target + offset,
+                    direction: -offset * rng.gen_range(1.0e-6..1.0),
+                }
+            })
+            .collect()
+    }
+
+    fn assert_matches_binary(bvh: &Bvh, wide: &WideBvh, bounds: &[Aabb], rays: &[Ray]) {
+        for ray in rays {
+            let expected = bvh.intersect(ray, 0.0, Float::INFINITY, |primitive, t_max| hit_box(bounds, primitive, ray, 0.0, t_max));
+            let actual = wide.intersect(ray, 0.0, Float::INFINITY, |primitive, t_max| hit_box(bounds, primitive, ray, 0.0, t_max));
+            assert!(expected.is_some());
+            assert_eq!(actual.map(|hit| hit.t), expected.map(|hit| hit.t));
+            assert!(wide.occluded(ray, 0.0, Float::INFINITY, |primitive| hit_box(bounds, primitive, ray, 0.0, Float::INFINITY).is_some()));
+        }
+    }
+
+    #[test]
+    #[cfg_attr(feature = "f32", ignore = "in single precision the binary reference misses far-origin rays itself")]
+    fn matches_binary_bvh_for_far_origins() {
+        let mut rng = SmallRng::seed_from_u64(7);
+        let bounds = far_boxes(&mut rng);
+        let bvh = Bvh::build(&bounds);
+        let wide = WideBvh::from_bvh(&bvh);
+        assert_matches_binary(&bvh, &wide, &bounds, &far_rays(&bounds, &mut rng));
+    }
+
+    #[test]
+    #[cfg_attr(feature = "f32", ignore = "in single precision the binary reference misses far-origin rays itself")]
+    fn refit_matches_binary_bvh() {
+        let mut rng = SmallRng::seed_from_u64(11);
+        let bounds = far_boxes(&mut rng);
+        let mut bvh = Bvh::build(&bounds);
+        let mut wide = WideBvh::from_bvh(&bvh);
+        let moved = bounds
+            .iter()
+            .map(|bounds| {
+                let offset = Vector::new(rng.gen_range(-50.0..50.0), rng.gen_range(-50.0..50.0), rng.gen_range(-50.0..50.0));
+                Aabb::new(bounds.min + offset, bounds.max + offset)
+            })
+            .collect::<Vec<Aabb>>();
+        bvh.refit(&moved);
+        wide.refit(&bvh);
+        assert_eq!(wide.bounding_box().min, bvh.bounding_box().min);
+        assert_matches_binary(&bvh, &wide, &moved, &far_rays(&moved, &mut rng));
+    }
+
+    fn random_vector(rng: &mut SmallRng, extent: Float) -> Vector {
+        Vector::new(rng.gen_range(-extent..extent), rng.gen_range(-extent..extent), rng.gen_range(-extent..extent))
+    }
+
+    /// Checks closest hits and shadow tests against testing every sphere.
+    fn assert_matches_brute_force(wide: &WideBvh, spheres: &[Sphere], rays: &[Ray]) {
+        for ray in rays {
+            let closest = |t_max: Float| spheres.iter().filter_map(|sphere| sphere.intersect(ray, 0.0, t_max)).map(|hit| hit.t).min_by(Float::total_cmp);
+            let actual = wide.intersect(ray, 0.0, Float::INFINITY, |primitive, t_max| spheres[primitive].intersect(ray, 0.0, t_max));
+            assert_eq!(actual.map(|hit| hit.t), closest(Float::INFINITY));
+            let occluded = wide.occluded(ray, 0.0, 40.0, |primitive| spheres[primitive].occluded(ray, 0.0, 40.0));
+            assert_eq!(occluded, closest(40.0).is_some());
+        }
+    }
+
+    #[test]
+    fn refit_matches_brute_force() {
+        let mut rng = SmallRng::seed_from_u64(13);
+        let mut spheres = (0..500)
+            .map(|_| Sphere {
+                center: random_vector(&mut rng, 50.0),
+                radius: rng.gen_range(0.2..3.0),
+                material: MaterialId::default(),
+            })
+            .collect::<Vec<Sphere>>();
+        let rays = (0..20_000)
+            .map(|_| Ray {
+                origin: random_vector(&mut rng, 80.0),
+                direction: random_vector(&mut rng, 1.0),
+            })
+            .collect::<Vec<Ray>>();
+        let bounds = |spheres: &[Sphere]| spheres.iter().map(Sphere::bounding_box).collect::<Vec<Aabb>>();
+        let mut bvh = Bvh::build(&bounds(&spheres));
+        let mut wide = WideBvh::from_bvh(&bvh);
+        assert_matches_brute_force(&wide, &spheres, &rays);
+
+        for sphere in &mut spheres {
+            sphere.center += random_vector(&mut rng, 5.0);
+        }
+        bvh.refit(&bounds(&spheres));
+        wide.refit(&bvh);
+        assert_matches_brute_force(&wide, &spheres, &rays);
+    }
+}